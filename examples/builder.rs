@@ -2,6 +2,7 @@ mod server {
     use optarg2chain::optarg_impl;
 
     #[derive(Debug)]
+    #[allow(dead_code)]
     pub struct Server {
         hostname: String,
         port: u16,
@@ -16,7 +17,7 @@ mod server {
             hostname: &'a str,
             port: u16,
             #[optarg_default] service_name: &'b str,
-            #[optarg(false)] enabled: bool,
+            #[optarg_flag] enabled: bool,
         ) -> Server {
             // Some processes to open the server...
             Server {
@@ -32,6 +33,7 @@ mod server {
 fn main() {
     let server = server::Server::new("example.com", 10000)
         .service_name("my-super-service")
+        .enabled()
         .build();
     println!("{:#?}", server);
 }
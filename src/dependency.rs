@@ -0,0 +1,196 @@
+//! Functions to order optional arguments by dependencies among their default values
+
+use crate::{recursion, Arg};
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use syn::fold::{self, Fold};
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+/// Sorts optional arguments so that each default value is evaluated after the arguments it refers to.
+/// Arguments without dependencies keep the declaration order.
+pub fn sort_by_dependency<'a>(opt_args: &[&'a Arg<'a>]) -> Result<Vec<&'a Arg<'a>>> {
+    let deps: Vec<Vec<usize>> = opt_args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let idents = referred_idents(&arg.opt.as_ref().unwrap().default_value);
+            opt_args
+                .iter()
+                .enumerate()
+                .filter(|(j, dep)| *j != i && idents.contains(dep.ident))
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect();
+
+    let mut sorted: Vec<usize> = vec![];
+    while sorted.len() < opt_args.len() {
+        let next = (0..opt_args.len())
+            .find(|i| !sorted.contains(i) && deps[*i].iter().all(|dep| sorted.contains(dep)));
+        match next {
+            Some(i) => sorted.push(i),
+            None => {
+                let cyclic: Vec<&Arg> = (0..opt_args.len())
+                    .filter(|i| !sorted.contains(i))
+                    .map(|i| opt_args[i])
+                    .collect();
+                let names: Vec<String> = cyclic
+                    .iter()
+                    .map(|arg| format!("`{}`", arg.ident))
+                    .collect();
+                return Err(Error::new(
+                    cyclic[0].opt.as_ref().unwrap().default_value.span(),
+                    format!(
+                        "(optarg2chain) default values of {} have cyclic dependency",
+                        names.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(sorted.into_iter().map(|i| opt_args[i]).collect())
+}
+
+/// Returns identifiers in the expression which can be variables. Names bound in the expression
+/// by closure parameters and local patterns are skipped in their scopes.
+pub fn referred_idents(expr: &syn::Expr) -> Vec<syn::Ident> {
+    let mut collector = IdentCollector {
+        bound: vec![],
+        idents: vec![],
+    };
+    collector.fold_expr(expr.clone());
+    collector.idents
+}
+
+// Collects identifiers which can be variables, and tracks names bound in the current scope
+struct IdentCollector {
+    bound: Vec<syn::Ident>,
+    idents: Vec<syn::Ident>,
+}
+
+impl IdentCollector {
+    fn push(&mut self, ident: &syn::Ident) {
+        if !self.bound.contains(ident) {
+            self.idents.push(ident.clone());
+        }
+    }
+
+    // Folds `f` with the names bound by `pats`, and removes them after that
+    fn scoped<'a, T>(
+        &mut self,
+        pats: impl IntoIterator<Item = &'a syn::Pat>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let len = self.bound.len();
+        for pat in pats {
+            self.bound.extend(recursion::pat_bindings(pat));
+        }
+        let result = f(self);
+        self.bound.truncate(len);
+        result
+    }
+}
+
+impl Fold for IdentCollector {
+    fn fold_expr_path(&mut self, expr: syn::ExprPath) -> syn::ExprPath {
+        if let (None, Some(ident)) = (&expr.qself, expr.path.get_ident()) {
+            self.push(ident);
+        }
+        expr
+    }
+
+    // Tokens of macros are not parsed, so every identifier which can be a variable is collected
+    fn fold_macro(&mut self, mac: syn::Macro) -> syn::Macro {
+        let mut idents = vec![];
+        collect_idents(mac.tokens.clone(), &mut idents);
+        for ident in &idents {
+            self.push(ident);
+        }
+        mac
+    }
+
+    fn fold_block(&mut self, block: syn::Block) -> syn::Block {
+        self.scoped(None, |this| fold::fold_block(this, block))
+    }
+
+    // The binding is visible in the rest of the enclosing block, which `fold_block` removes
+    fn fold_local(&mut self, local: syn::Local) -> syn::Local {
+        let local = fold::fold_local(self, local);
+        self.bound.extend(recursion::pat_bindings(&local.pat));
+        local
+    }
+
+    fn fold_expr_closure(&mut self, closure: syn::ExprClosure) -> syn::ExprClosure {
+        let inputs = closure.inputs.clone();
+        self.scoped(&inputs, |this| fold::fold_expr_closure(this, closure))
+    }
+
+    fn fold_arm(&mut self, arm: syn::Arm) -> syn::Arm {
+        let pat = arm.pat.clone();
+        self.scoped(Some(&pat), |this| fold::fold_arm(this, arm))
+    }
+
+    fn fold_expr_for_loop(&mut self, mut expr: syn::ExprForLoop) -> syn::ExprForLoop {
+        expr.expr = Box::new(self.fold_expr(*expr.expr));
+        let body = expr.body;
+        expr.body = self.scoped(Some(&expr.pat), |this| this.fold_block(body));
+        expr
+    }
+
+    fn fold_expr_if(&mut self, mut expr: syn::ExprIf) -> syn::ExprIf {
+        expr.cond = Box::new(self.fold_expr(*expr.cond));
+        let then_branch = expr.then_branch;
+        expr.then_branch = self.scoped(let_pat(&expr.cond), |this| this.fold_block(then_branch));
+        expr.else_branch = expr
+            .else_branch
+            .map(|(else_token, else_expr)| (else_token, Box::new(self.fold_expr(*else_expr))));
+        expr
+    }
+
+    fn fold_expr_while(&mut self, mut expr: syn::ExprWhile) -> syn::ExprWhile {
+        expr.cond = Box::new(self.fold_expr(*expr.cond));
+        let body = expr.body;
+        expr.body = self.scoped(let_pat(&expr.cond), |this| this.fold_block(body));
+        expr
+    }
+
+    // Items in the expression have their own scopes
+    fn fold_item(&mut self, item: syn::Item) -> syn::Item {
+        item
+    }
+}
+
+// Returns the pattern of `if let` or `while let` condition
+fn let_pat(cond: &syn::Expr) -> Option<&syn::Pat> {
+    match cond {
+        syn::Expr::Let(expr) => Some(&expr.pat),
+        _ => None,
+    }
+}
+
+// Collects identifiers which can be variables. Identifiers after `.` or `::` and field names before `:` are skipped.
+fn collect_idents(tokens: TokenStream, idents: &mut Vec<syn::Ident>) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            TokenTree::Ident(ident) => {
+                let after_dot = i >= 1 && is_punct(&tokens[i - 1], '.');
+                let after_path_sep =
+                    i >= 2 && is_punct(&tokens[i - 1], ':') && is_punct(&tokens[i - 2], ':');
+                let before_colon = matches!(
+                    tokens.get(i + 1),
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' && p.spacing() == Spacing::Alone
+                );
+                if !after_dot && !after_path_sep && !before_colon {
+                    idents.push(ident.clone());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn is_punct(token: &TokenTree, c: char) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == c)
+}
@@ -1,29 +1,241 @@
 pub struct DocAttrs {
     pub doc_builder_struct: syn::Attribute,
-    pub doc_setter: Vec<syn::Attribute>,
     pub doc_terminal_method: syn::Attribute,
 }
 
-/// Generates document attributes for struct and methods
-pub fn generate_doc(func_name: &syn::Ident, opt_ident: &[&syn::Ident]) -> DocAttrs {
+/// Generates document attributes for struct and terminal method
+pub fn generate_doc(func_name: &syn::Ident) -> DocAttrs {
     let msg = format!("Argument builder struct for `{}`.", func_name);
     let doc_builder_struct = syn::parse_quote! { #[doc = #msg] };
 
-    let doc_setter: Vec<syn::Attribute> = opt_ident
-        .iter()
-        .map(|i| {
-            let msg = format!("Sets optional argument `{}`.", i);
-            let a: syn::Attribute = syn::parse_quote! { #[doc = #msg] };
-            a
-        })
-        .collect();
-
     let msg = format!("Executes `{}` and get the result.", func_name);
     let doc_terminal_method = syn::parse_quote! { #[doc = #msg] };
 
     DocAttrs {
         doc_builder_struct,
-        doc_setter,
         doc_terminal_method,
     }
 }
+
+/// Generates document attribute for terminal method returning `Result`
+pub fn try_terminal_method(terminal_ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Executes like `{}`, but returns an error if an argument check failed.",
+        terminal_ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for the error type
+pub fn error_type(func_name: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Argument error for `{}`.", func_name);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for optional argument setter
+pub fn setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets optional argument `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for required argument setter
+pub fn required_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets required argument `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter configuring the nested builder
+pub fn nested_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` by configuring its builder with a closure.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter taking `Option`
+pub fn maybe_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` to the given `Option` value.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter that is effective only for `Some`
+pub fn maybe_some_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` if the given value is `Some`.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for conditional setter
+pub fn if_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets optional argument `{}` if `cond` is true.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for flag setter
+pub fn flag_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets flag `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for flag unsetter
+pub fn flag_unsetter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Unsets flag `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for flag setter taking `Option`
+pub fn flag_maybe_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets flag `{}` to the given value if it is `Some`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for conditional flag setter
+pub fn flag_if_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets flag `{}` if `cond` is true.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter pushing an element
+pub fn push_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Adds an element to optional argument `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter inserting a key-value pair
+pub fn insert_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Adds a key-value pair to optional argument `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter extending by an iterator
+pub fn extend_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Adds elements of the given iterator to optional argument `{}`.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter with `TryInto`
+pub fn try_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` by fallible conversion. The conversion error is returned by the terminal method.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter taking a closure
+pub fn lazy_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` by a closure. The closure is called by the terminal method.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter taking `&mut self`
+pub fn in_place_setter(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets optional argument `{}` in place.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for terminal method cloning the builder
+pub fn cloned_terminal_method(terminal_ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Executes `{}` with a clone of this builder.",
+        terminal_ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for function taking all arguments
+pub fn full_fn(func_name: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Executes `{}` with all arguments given without the builder.",
+        func_name
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for terminal method blocking the current thread
+pub fn blocking_terminal_method(terminal_ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Executes `{}` and blocks the current thread until the future completes.",
+        terminal_ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for method to reset an argument
+pub fn reset(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Resets optional argument `{}` to use the default value.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for method to reset all arguments
+pub fn reset_all() -> syn::Attribute {
+    syn::parse_quote! { #[doc = "Resets all optional arguments to use the default values."] }
+}
+
+/// Generates document attribute for setter of a flattened struct field
+pub fn flatten_setter(ident: &syn::Ident, struct_ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Sets field `{}` of `{}`.", ident, struct_ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for setter of an enum variant
+pub fn variant_setter(
+    ident: &syn::Ident,
+    enum_ident: &syn::Ident,
+    variant: &syn::Ident,
+) -> syn::Attribute {
+    let msg = format!(
+        "Sets optional argument `{}` to `{}::{}`.",
+        ident, enum_ident, variant
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for associated constant of a default value
+pub fn default_const(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!("Default value of optional argument `{}`.", ident);
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for function returning a default value
+pub fn default_fn(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Returns the default value of optional argument `{}`.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for function overriding a default value
+pub fn with_default(ident: &syn::Ident) -> syn::Attribute {
+    let msg = format!(
+        "Calls `f` with the default value of optional argument `{}` replaced by `value` on the current thread.",
+        ident
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
+
+/// Generates document attribute for preset method
+pub fn preset(args: &[&syn::Ident]) -> syn::Attribute {
+    let names: Vec<String> = args.iter().map(|arg| format!("`{}`", arg)).collect();
+    let msg = format!(
+        "Sets optional arguments {} to the preset values.",
+        names.join(", ")
+    );
+    syn::parse_quote! { #[doc = #msg] }
+}
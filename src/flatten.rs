@@ -0,0 +1,187 @@
+//! Functions to generate setters for fields of flattened struct arguments
+
+use crate::setter::SetterParam;
+use crate::{doc, Arg, OptArgKind, SetterFlavor};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+const ERR_MSG_GENERIC_STRUCT: &str =
+    "(optarg2chain) `OptargFlatten` for generic structs is not supported";
+const ERR_MSG_NOT_NAMED_FIELDS: &str =
+    "(optarg2chain) `OptargFlatten` needs a struct with named fields";
+
+// Returns the name of the macro and the trait giving access to the fields, generated by
+// `OptargFlatten` derive. They share the name so that a single `use` imports both.
+fn callback_macro_ident(struct_ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_flatten_{}", struct_ident)
+}
+
+// Returns the name of the macro before it is re-exported with the name shared with the trait
+fn local_macro_ident(struct_ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_flatten_macro_{}", struct_ident)
+}
+
+// Returns the name of the trait method returning the reference to the field
+fn field_accessor_ident(field_ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_field_{}", field_ident)
+}
+
+// Replaces the last segment of the path of the struct type
+fn replace_last_segment(path: &syn::Path, f: fn(&syn::Ident) -> syn::Ident) -> Option<syn::Path> {
+    let mut path = path.clone();
+    let segment = path.segments.last_mut()?;
+    segment.ident = f(&segment.ident);
+    segment.arguments = syn::PathArguments::None;
+    Some(path)
+}
+
+/// Returns the path of the macro generated by `OptargFlatten` for the given struct type
+pub fn callback_macro_path(ty: &syn::Type) -> Option<syn::Path> {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            replace_last_segment(path, callback_macro_ident)
+        }
+        _ => None,
+    }
+}
+
+/// Generates the trait giving access to the fields and the macro passing field names to
+/// `__optarg_flatten_setters`
+pub fn derive(input: &syn::DeriveInput) -> Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), ERR_MSG_GENERIC_STRUCT));
+    }
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => return Err(Error::new(input.ident.span(), ERR_MSG_NOT_NAMED_FIELDS)),
+    };
+    let vis = &input.vis;
+    let struct_ident = &input.ident;
+    let trait_ident = callback_macro_ident(struct_ident);
+    let local_macro_ident = local_macro_ident(struct_ident);
+    let field_ident: Vec<&syn::Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let field_ty = fields.iter().map(|f| &f.ty);
+    let accessor: Vec<syn::Ident> = field_ident
+        .iter()
+        .map(|ident| field_accessor_ident(ident))
+        .collect();
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis trait #trait_ident {
+            #(
+                type #field_ident;
+                fn #accessor(&mut self) -> &mut Self::#field_ident;
+            )*
+        }
+
+        impl #trait_ident for #struct_ident {
+            #(
+                type #field_ident = #field_ty;
+                fn #accessor(&mut self) -> &mut Self::#field_ident {
+                    &mut self.#field_ident
+                }
+            )*
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #local_macro_ident {
+            ($($input:tt)*) => {
+                ::optarg2chain::__optarg_flatten_setters! { [#(#field_ident)*] $($input)* }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #local_macro_ident as #trait_ident;
+    })
+}
+
+/// Generates invocations of the macros generated by `OptargFlatten` in the impl block of the builder struct
+pub fn generate_flatten_setters(vis: &syn::Visibility, opt_args: &[&Arg]) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .filter_map(|arg| {
+            let opt = arg.opt.as_ref().unwrap();
+            match &opt.kind {
+                OptArgKind::Flatten(path) => {
+                    let ident = arg.ident;
+                    let ty = arg.ty;
+                    let setter = opt.setter;
+                    Some(quote! { #path! { #vis #ident #setter #ty } })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Input of `__optarg_flatten_setters` macro
+pub struct FlattenSetters {
+    fields: Vec<syn::Ident>,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    setter: SetterFlavor,
+    ty: syn::TypePath,
+}
+
+impl Parse for FlattenSetters {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let content;
+        syn::bracketed!(content in input);
+        let mut fields = vec![];
+        while !content.is_empty() {
+            fields.push(content.parse()?);
+        }
+        Ok(FlattenSetters {
+            fields,
+            vis: input.parse()?,
+            ident: input.parse()?,
+            setter: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+/// Generates a setter for each field, taking the value as the setter flavor of the argument.
+/// Unset fields of the struct are filled by `Default::default()`.
+pub fn generate_setters(input: &FlattenSetters) -> TokenStream {
+    let FlattenSetters {
+        fields,
+        vis,
+        ident,
+        setter,
+        ty,
+    } = input;
+    let trait_path = replace_last_segment(&ty.path, callback_macro_ident).unwrap();
+    let struct_ident = &ty.path.segments.last().unwrap().ident;
+    let setters = fields.iter().map(|field| {
+        let accessor = field_accessor_ident(field);
+        let doc_setter = doc::flatten_setter(field, struct_ident);
+        let field_ty: syn::Type = syn::parse_quote! { <#ty as #trait_path>::#field };
+        let SetterParam {
+            generics,
+            param_ty,
+            where_clause,
+            convert,
+        } = SetterParam::new(*setter, &field_ty);
+        quote! {
+            #doc_setter
+            #vis fn #field #generics(mut self, value: #param_ty) -> Self #where_clause {
+                let value = #convert;
+                let target = self.#ident.get_or_insert_with(<#ty as core::default::Default>::default);
+                *<#ty as #trait_path>::#accessor(target) = value;
+                self
+            }
+        }
+    });
+    quote! { #(#setters)* }
+}
@@ -68,7 +68,7 @@ pub struct SelfReplace<'a>(pub &'a syn::Type);
 impl<'a> Fold for SelfReplace<'a> {
     fn fold_type(&mut self, ty: syn::Type) -> syn::Type {
         if let Some(ident) = get_ident_from_type(&ty) {
-            if ident.to_string() == "Self" {
+            if ident == "Self" {
                 return self.0.clone();
             }
         }
@@ -132,6 +132,14 @@ impl Fold for TypeFilterBuilder {
     }
 }
 
+/// Adds the lifetime of closures given to `_with` suffixed setters to the generics of the builder struct
+pub fn add_lazy_lifetime(generics: &mut syn::Generics) {
+    let lifetime = syn::LifetimeDef::new(crate::lazy_lifetime());
+    generics
+        .params
+        .insert(0, syn::GenericParam::Lifetime(lifetime));
+}
+
 /// Generates a type holder for struct. exapmle: `PhantomData<fn() -> (&'a (), T, U)>`
 pub fn generate_type_holder(generics: &syn::Generics) -> syn::Type {
     let lifetimes: Vec<&syn::Lifetime> = generics.lifetimes().map(|l| &l.lifetime).collect();
@@ -145,16 +153,14 @@ pub fn generate_type_holder(generics: &syn::Generics) -> syn::Type {
 }
 
 fn get_ident_from_type(ty: &syn::Type) -> Option<&syn::Ident> {
-    match ty {
-        syn::Type::Path(syn::TypePath {
-            qself: None,
-            ref path,
-        }) => {
-            if let Some(ident) = path.get_ident() {
-                return Some(ident);
-            }
+    if let syn::Type::Path(syn::TypePath {
+        qself: None,
+        ref path,
+    }) = ty
+    {
+        if let Some(ident) = path.get_ident() {
+            return Some(ident);
         }
-        _ => (),
     }
     None
 }
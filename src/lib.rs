@@ -43,6 +43,461 @@
 //! );
 //! ```
 //!
+//! Each setter has two companions. `maybe_` prefixed setter takes `Option` and sets the value only if it is `Some`, and `_if` suffixed setter sets the value only if the given condition is true.
+//! ```
+//! # use optarg2chain::optarg_fn;
+//! # #[optarg_fn(JoinStringBuilder, exec)]
+//! # fn join_strings(
+//! #     mut a: String,
+//! #     #[optarg_default] b: String,
+//! #     #[optarg("ccc".to_owned())] c: String,
+//! # ) -> String {
+//! #     a.push_str(&b);
+//! #     a.push_str(&c);
+//! #     a
+//! # }
+//! let b: Option<String> = None;
+//! assert_eq!(
+//!     join_strings("xxx".to_owned())
+//!         .maybe_b(b) // `b` is not changed because the given value is `None`
+//!         .c_if(true, "zzz")
+//!         .exec(),
+//!     "xxxzzz"
+//! );
+//! ```
+//!
+//! `reset_` prefixed method resets an optional argument to use its default value again, and `reset_all` resets all optional arguments. Therefore an optional argument cannot be named `all`.
+//!
+//! Default values can refer to other arguments regardless of the order of the parameters. They are evaluated in the order of their dependencies, and cyclic dependency is a compile error. Names bound inside a default value, such as closure parameters and `let` bindings, are not treated as references to arguments.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(RectBuilder, exec)]
+//! fn rect(#[optarg(width * 2)] height: u32, #[optarg(10)] width: u32) -> (u32, u32) {
+//!     (width, height)
+//! }
+//!
+//! assert_eq!(rect().exec(), (10, 20));
+//! assert_eq!(rect().width(3u32).exec(), (3, 6));
+//! ```
+//!
+//! `#[optarg(const = expr)]` gives a constant default value, exposed as an associated constant of the builder struct named by `DEFAULT_` followed by the argument name in upper case. The builder struct also has `default_` prefixed function for each default value not referring to other arguments.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(WaitBuilder, exec)]
+//! fn wait(#[optarg(const = 30)] timeout_secs: u64, #[optarg(1 + 1)] retries: u32) -> (u64, u32) {
+//!     (timeout_secs, retries)
+//! }
+//!
+//! assert_eq!(WaitBuilder::DEFAULT_TIMEOUT_SECS, 30);
+//! assert_eq!(WaitBuilder::default_retries(), 2);
+//! ```
+//!
+//! # `Option` arguments
+//! `#[optarg_none]` can be used for an argument of `Option<T>` type. Its default value is `None`, and the setter takes a value converted into `T`. `maybe_` prefixed setter for this argument takes `Option<T>` and passes it as it is.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(TakeBuilder, exec)]
+//! fn take(v: Vec<i32>, #[optarg_none] limit: Option<usize>) -> Vec<i32> {
+//!     v.into_iter().take(limit.unwrap_or(usize::MAX)).collect()
+//! }
+//!
+//! assert_eq!(take(vec![1, 2, 3]).exec(), [1, 2, 3]);
+//! assert_eq!(take(vec![1, 2, 3]).limit(2usize).exec(), [1, 2]);
+//! assert_eq!(take(vec![1, 2, 3]).maybe_limit(None).exec(), [1, 2, 3]);
+//! ```
+//!
+//! # Flag arguments
+//! `#[optarg_flag]` can be used for an argument of `bool` type. Its default value is `false`, and the setter takes no argument to set it to `true`. Use `#[optarg_flag(true)]` to make the default value `true`, then `no_` prefixed setter is also generated to set it to `false`.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(ShowBuilder, exec)]
+//! fn show(#[optarg_flag] verbose: bool, #[optarg_flag(true)] color: bool) -> (bool, bool) {
+//!     (verbose, color)
+//! }
+//!
+//! assert_eq!(show().exec(), (false, true));
+//! assert_eq!(show().verbose().no_color().exec(), (true, false));
+//! ```
+//!
+//! # Collection arguments
+//! `#[optarg_collect]` can be used for an argument of a collection type implementing `Default` and `Extend`. Its default value is given by `Default::default()`. In addition to the setter replacing the whole value, `push_`, `insert_` and `extend_` prefixed setters are generated to add an element, a key-value pair and elements of an iterator.
+//! ```
+//! use optarg2chain::optarg_fn;
+//! use std::collections::HashMap;
+//!
+//! #[optarg_fn(RequestBuilder, send)]
+//! fn request(
+//!     #[optarg_collect] tags: Vec<String>,
+//!     #[optarg_collect] headers: HashMap<String, String>,
+//! ) -> (Vec<String>, HashMap<String, String>) {
+//!     (tags, headers)
+//! }
+//!
+//! let (tags, headers) = request()
+//!     .push_tags("a".to_owned())
+//!     .extend_tags(vec!["b".to_owned(), "c".to_owned()])
+//!     .insert_headers("Accept".to_owned(), "*/*".to_owned())
+//!     .send();
+//! assert_eq!(tags, ["a", "b", "c"]);
+//! assert_eq!(headers["Accept"], "*/*");
+//! ```
+//!
+//! # Flattened struct arguments
+//! `#[optarg_flatten]` can be used for an argument of a struct type deriving `OptargFlatten` and `Default`. Each field of the struct becomes a setter of the builder, and the terminal method fills unset fields with the values of `Default::default()`. A field with the same name as another setter of the builder is a compile error. The field setters take values as the `setter` option given to `optarg_fn` or `optarg_method`. The struct needs to be defined in the same crate. The derive generates a hidden item named `_optarg_flatten_` followed by the struct name next to the struct. If the struct type is written without its module path in the function, the item needs to be imported together with the struct, like `use options::{RenderOptions, _optarg_flatten_RenderOptions};`.
+//! ```
+//! use optarg2chain::{optarg_fn, OptargFlatten};
+//!
+//! #[derive(OptargFlatten, Default)]
+//! pub struct RenderOptions {
+//!     width: u32,
+//!     height: u32,
+//! }
+//!
+//! #[optarg_fn(RenderBuilder, exec)]
+//! fn render(#[optarg_flatten] opts: RenderOptions) -> (u32, u32) {
+//!     (opts.width, opts.height)
+//! }
+//!
+//! assert_eq!(render().height(24u32).exec(), (0, 24));
+//! ```
+//!
+//! # Nested builder arguments
+//! `#[optarg_nested(ctor, BuilderType, terminal)]` can be used for an argument built by another builder. `ctor` is an expression to create the nested builder, and the default value is given by `ctor.terminal()`. The setter takes a closure configuring the nested builder, and the terminal method of the nested builder is called automatically.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(RetryBuilder, build)]
+//! fn retry(#[optarg(3u32)] max: u32, #[optarg(10u64)] backoff: u64) -> (u32, u64) {
+//!     (max, backoff)
+//! }
+//!
+//! #[optarg_fn(FetchBuilder, exec)]
+//! fn fetch(#[optarg_nested(retry(), RetryBuilder, build)] retry: (u32, u64)) -> (u32, u64) {
+//!     retry
+//! }
+//!
+//! assert_eq!(fetch().exec(), (3, 10));
+//! assert_eq!(fetch().retry(|r| r.max(5u32)).exec(), (5, 10));
+//! ```
+//!
+//! # Enum variant setters
+//! `#[optarg_variants]` can be used for an argument of a fieldless enum type deriving `OptargVariants`, together with `#[optarg(expr)]` or `#[optarg_default]`. A setter is generated for each variant, named by the argument name and the variant name in snake case. The enum needs to be defined in the same crate. The derive generates a hidden macro named `_optarg_variants_` followed by the enum name next to the enum. If the enum type is written without its module path in the function, the macro needs to be imported together with the enum, like `use mode::{Mode, _optarg_variants_Mode};`.
+//! ```
+//! use optarg2chain::{optarg_fn, OptargVariants};
+//!
+//! #[derive(OptargVariants)]
+//! enum Mode {
+//!     Fast,
+//!     Safe,
+//! }
+//!
+//! #[optarg_fn(CompressBuilder, exec)]
+//! fn compress(#[optarg(Mode::Fast)] #[optarg_variants] mode: Mode) -> bool {
+//!     matches!(mode, Mode::Safe)
+//! }
+//!
+//! assert!(compress().mode_safe().exec());
+//! ```
+//!
+//! # Fallible conversion
+//! `#[optarg(expr, try_into)]` generates `try_` prefixed setter taking a value converted by `TryInto`. When the conversion fails, the error is kept in the builder and `try_` prefixed terminal method returns it. The error type is generated with the name of the builder struct followed by `Error`. The original terminal method panics in this case. Setting the argument again discards the kept error. The error of `TryInto` itself is not kept, so it needs no trait implementation.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(ListenBuilder, exec)]
+//! fn listen(#[optarg(80, try_into)] port: u16) -> u16 {
+//!     port
+//! }
+//!
+//! assert_eq!(listen().try_port(8080u32).exec(), 8080);
+//! assert!(matches!(
+//!     listen().try_port(100000u32).try_exec(),
+//!     Err(ListenBuilderError::Conversion { arg: "port", .. })
+//! ));
+//! ```
+//!
+//! # Validation
+//! `#[optarg(expr, validate = path)]` gives a validator function `fn(&T) -> Result<(), E>` to an argument, and `#[optarg(expr, range = a..=b)]` checks that the value is contained in the range. `validate = path` given to `optarg_fn` or `optarg_method` gives a validator taking references to all arguments. Validators are called by the terminal method before the original function, and `try_` prefixed terminal method returns the error naming the failing argument. The error returned by the validator is discarded, so its type needs no trait implementation. The original terminal method panics if a check fails.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! fn even(n: &u16) -> Result<(), &'static str> {
+//!     if n % 2 == 0 { Ok(()) } else { Err("odd number") }
+//! }
+//!
+//! #[optarg_fn(ServeBuilder, exec)]
+//! fn serve(
+//!     #[optarg(8080, validate = even)] port: u16,
+//!     #[optarg(1, range = 1..=64)] workers: u8,
+//! ) -> String {
+//!     format!("{} {}", port, workers)
+//! }
+//!
+//! assert_eq!(
+//!     serve().port(8081u16).try_exec(),
+//!     Err(ServeBuilderError::Validation { arg: "port" })
+//! );
+//! assert_eq!(
+//!     serve().workers(100).try_exec(),
+//!     Err(ServeBuilderError::OutOfRange { arg: "workers" })
+//! );
+//! ```
+//!
+//! # Argument groups
+//! `conflicts(a, b, ..)` given to `optarg_fn` or `optarg_method` makes the optional arguments mutually exclusive, and `requires(a => b)` makes `a` require `b`. Violations are checked by the terminal method, and `try_` prefixed terminal method returns the error naming the arguments. The original terminal method panics in this case. An argument of `optarg_none` is regarded as set only if it is set to `Some`. The argument names are checked at compile time.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(WaitBuilder, exec, conflicts(timeout, deadline))]
+//! fn wait(#[optarg_none] timeout: Option<u32>, #[optarg_none] deadline: Option<u32>) -> u32 {
+//!     timeout.or(deadline).unwrap_or(0)
+//! }
+//!
+//! assert_eq!(
+//!     wait().timeout(1u32).deadline(2u32).try_exec(),
+//!     Err(WaitBuilderError::Conflict { arg: "timeout", conflicting: "deadline" })
+//! );
+//! ```
+//!
+//! # Presets
+//! `#[optarg_preset(name, a = expr, ..)]` placed after `optarg_fn` or `optarg_method` generates a method `name` setting the optional arguments at once. Values must have the types of the arguments (the inner type for `optarg_none`), and setters called later override them. The argument names are checked at compile time.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(EncodeBuilder, exec)]
+//! #[optarg_preset(fast, quality = 1, threads = 8)]
+//! #[optarg_preset(best, quality = 9)]
+//! fn encode(#[optarg(5)] quality: u8, #[optarg(1)] threads: u32) -> (u8, u32) {
+//!     (quality, threads)
+//! }
+//!
+//! assert_eq!(encode().fast().exec(), (1, 8));
+//! assert_eq!(encode().best().exec(), (9, 1));
+//! assert_eq!(encode().fast().quality(3u8).exec(), (3, 8));
+//! ```
+//!
+//! # Setter parameter type
+//! Setters take a value converted by `Into` by default. `setter` option changes it for an argument, or for all arguments of the function when given to `optarg_fn` or `optarg_method`.
+//! * `setter = into`: takes a value converted by `Into` (default)
+//! * `setter = exact`: takes a value of the argument type. This helps type inference of numeric literals, `None` and closures.
+//! * `setter = clone_from_ref`: takes a reference and clones it
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(ScaleBuilder, exec, setter = exact)]
+//! fn scale(
+//!     x: u8,
+//!     #[optarg(2)] factor: u8,
+//!     #[optarg(None)] offset: Option<u8>,
+//!     #[optarg(String::new(), setter = into)] unit: String,
+//! ) -> String {
+//!     format!("{}{}", x * factor + offset.unwrap_or(0), unit)
+//! }
+//!
+//! assert_eq!(scale(3).factor(3).offset(Some(1)).unit("px").exec(), "10px");
+//! ```
+//!
+//! # Lazy values
+//! `#[optarg(expr, lazy)]` generates `_with` suffixed setter taking a closure. The closure is called by the terminal method only if no other value is set after it. The closure may borrow local values, so the builder struct gets a lifetime parameter for it before the other generic parameters. The closure is boxed by `alloc` crate.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(GreetBuilder, exec)]
+//! fn greet(#[optarg("world".to_owned(), lazy)] name: String) -> String {
+//!     format!("Hello, {}!", name)
+//! }
+//!
+//! let names = ["Alice", "Bob"];
+//! assert_eq!(greet().name_with(|| names.join(" and ")).exec(), "Hello, Alice and Bob!");
+//! assert_eq!(greet().exec(), "Hello, world!");
+//! ```
+//!
+//! # Async default values
+//! `#[optarg(async expr)]` for an async function or method gives a future as the default value. The terminal method awaits it only if no value is set. An async block is given without the extra `async`, as `#[optarg(async { .. })]`.
+//! ```
+//! use optarg2chain::optarg_fn;
+//! # use futures::executor::block_on;
+//!
+//! async fn load_token() -> String {
+//!     "loaded".to_owned()
+//! }
+//!
+//! #[optarg_fn(RequestBuilder, exec)]
+//! async fn request(#[optarg(async load_token())] token: String) -> String {
+//!     token
+//! }
+//!
+//! assert_eq!(block_on(request().exec()), "loaded");
+//! assert_eq!(block_on(request().token("given").exec()), "given");
+//! ```
+//!
+//! # Awaiting builders
+//! `into_future` option given to `optarg_fn` or `optarg_method` for an async function implements `IntoFuture` for the builder struct, so the builder can be awaited without calling the terminal method. The future is boxed, and lives as long as the first lifetime parameter of the builder or `'static`. When the builder has several lifetime parameters, the others must outlive the first one. The lifetime of closures given to `_with` suffixed setters comes first if the builder has `lazy` arguments. The future is `Send` only if `boxed_future` is also given. This option cannot be used with `impl Trait` return type, and needs `std` feature.
+//! ```
+//! # #[cfg(feature = "std")]
+//! # fn main() {
+//! use optarg2chain::optarg_fn;
+//! # use futures::executor::block_on;
+//!
+//! #[optarg_fn(FetchBuilder, exec, into_future)]
+//! async fn fetch<'a>(url: &'a str, #[optarg(3)] retries: u8) -> String {
+//!     format!("{}:{}", url, retries)
+//! }
+//!
+//! # block_on(async {
+//! assert_eq!(fetch("a").retries(1u8).await, "a:1");
+//! assert_eq!(fetch("a").exec().await, "a:3");
+//! # });
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Boxed futures
+//! `boxed_future` option given to `optarg_fn` or `optarg_method` for an async function makes the terminal method return `Pin<Box<dyn Future<Output = T> + Send + 'a>>`, where `'a` is the first lifetime parameter of the builder or `'static`. This allows recursive calls and storing the futures in trait objects. `boxed_future(?Send)` removes `Send` bound. This option needs `std` feature.
+//! ```
+//! # #[cfg(feature = "std")]
+//! # fn main() {
+//! use optarg2chain::optarg_fn;
+//! # use futures::executor::block_on;
+//!
+//! #[optarg_fn(FibBuilder, exec, boxed_future)]
+//! async fn fib(n: u32, #[optarg(0)] offset: u32) -> u32 {
+//!     if n < 2 {
+//!         n + offset
+//!     } else {
+//!         fib(n - 1).exec().await + fib(n - 2).exec().await + offset
+//!     }
+//! }
+//!
+//! # block_on(async {
+//! assert_eq!(fib(10).exec().await, 55);
+//! # });
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Blocking terminal methods
+//! `blocking` option given to `optarg_fn` or `optarg_method` for an async function generates `_blocking` suffixed terminal method. It drives the future on the current thread by a small built-in executor, so no async runtime is needed. This option needs `std` feature.
+//! ```
+//! # #[cfg(feature = "std")]
+//! # fn main() {
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(LoadBuilder, exec, blocking)]
+//! async fn load(#[optarg(1)] id: u32) -> u32 {
+//!     id * 2
+//! }
+//!
+//! assert_eq!(load().id(3u32).exec_blocking(), 6);
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Environment variable defaults
+//! `#[optarg(env = "VAR", default = expr)]` reads the default value from the environment variable `VAR` by `FromStr`, and uses `expr` if the variable is not set. `env_error` option decides the behavior when the value cannot be parsed. This option needs `std` feature.
+//! * `env_error = fallback`: uses `expr` (default)
+//! * `env_error = panic`: panics
+//! * `env_error = error`: `try_` prefixed terminal method returns the error, and the original terminal method panics
+//! ```
+//! # #[cfg(feature = "std")]
+//! # fn main() {
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(ServeBuilder, exec)]
+//! fn serve(#[optarg(env = "APP_PORT", default = 8080, env_error = error)] port: u16) -> u16 {
+//!     port
+//! }
+//!
+//! std::env::set_var("APP_PORT", "x");
+//! assert_eq!(
+//!     serve().try_exec(),
+//!     Err(ServeBuilderError::Env { arg: "port", var: "APP_PORT" })
+//! );
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Overridable defaults
+//! `overridable` option given to `optarg_fn` or `optarg_method` generates `with_default_` prefixed functions of the builder struct. They replace the default value while the given closure runs on the current thread. Types of optional arguments must be `Clone` and `'static`, and borrowing types are rejected at compile time. Generic builders keep a separate override for each type argument. This option needs `std` feature.
+//! ```
+//! # #[cfg(feature = "std")]
+//! # fn main() {
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(JoinStringsBuilder, exec, overridable)]
+//! fn join_strings(mut a: String, #[optarg("ccc".to_owned())] c: String) -> String {
+//!     a.push_str(&c);
+//!     a
+//! }
+//!
+//! let joined = JoinStringsBuilder::with_default_c("zzz", || join_strings("aaa".into()).exec());
+//! assert_eq!(joined, "aaazzz");
+//! assert_eq!(join_strings("aaa".into()).exec(), "aaaccc");
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+//!
+//! # In-place setters
+//! `in_place` option given to `optarg_fn` or `optarg_method` generates `set_` prefixed setters taking `&mut self`. The builder struct implements `Clone` if all fields are `Clone`, and `_cloned` suffixed terminal method executes with a clone of the builder.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(SumBuilder, exec, in_place)]
+//! fn sum(#[optarg(0)] a: i32, #[optarg(0)] b: i32) -> i32 {
+//!     a + b
+//! }
+//!
+//! let mut builder = sum();
+//! for i in 0..3 {
+//!     if i % 2 == 0 {
+//!         builder.set_a(i);
+//!     }
+//! }
+//! assert_eq!(builder.exec_cloned(), 2);
+//! builder.set_b(1);
+//! assert_eq!(builder.exec(), 3);
+//! ```
+//!
+//! # Setters for required arguments
+//! `required_setters` option given to `optarg_fn` or `optarg_method` generates setters for required arguments too. They overwrite the values given to the constructor function.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(PointBuilder, build, required_setters)]
+//! fn point(x: i32, y: i32, #[optarg(0)] z: i32) -> (i32, i32, i32) {
+//!     (x, y, z)
+//! }
+//!
+//! assert_eq!(point(1, 2).x(3).build(), (3, 2, 0));
+//! ```
+//!
+//! # Calls with all arguments
+//! In the function body, a call of the function itself with all arguments calls the original function directly. Local variables, closure parameters and items with the same name are called as they are. For methods, `Self::name(..)` and `self.name(..)` calls are handled in the same way. `full` option given to `optarg_fn` or `optarg_method` also generates `_full` suffixed function taking all arguments. It has its own document, and keeps the other attributes of the original function such as `#[inline]` and `#[allow(..)]`.
+//! ```
+//! use optarg2chain::optarg_fn;
+//!
+//! #[optarg_fn(SumToBuilder, exec, full)]
+//! fn sum_to(n: u32, #[optarg(0)] acc: u32) -> u32 {
+//!     if n == 0 {
+//!         acc
+//!     } else {
+//!         sum_to(n - 1, acc + n)
+//!     }
+//! }
+//!
+//! assert_eq!(sum_to(4).exec(), 10);
+//! assert_eq!(sum_to_full(4, 5), 15);
+//! ```
+//!
 //! # Method with optional arguments
 //! Use `#[optarg_impl]` and `#[optarg_method(BuilderStructName, terminal_method_name)]` for methods in `impl`
 //! ```
@@ -82,13 +537,20 @@
 
 extern crate proc_macro;
 
+mod dependency;
 mod doc;
+mod flatten;
 mod generics;
+mod overrides;
+mod recursion;
+mod setter;
+mod terminal;
+mod variants;
 
 use generics::*;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::fold::Fold;
 use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
@@ -97,6 +559,13 @@ use syn::{Error, Result};
 const ATTR_PREFIX: &str = "optarg";
 const ATTR_NAME_OPT_ARG: &str = "optarg";
 const ATTR_NAME_DEFAULT_ARG: &str = "optarg_default";
+const ATTR_NAME_NONE_ARG: &str = "optarg_none";
+const ATTR_NAME_FLAG_ARG: &str = "optarg_flag";
+const ATTR_NAME_COLLECT_ARG: &str = "optarg_collect";
+const ATTR_NAME_FLATTEN_ARG: &str = "optarg_flatten";
+const ATTR_NAME_NESTED_ARG: &str = "optarg_nested";
+const ATTR_NAME_VARIANTS_ARG: &str = "optarg_variants";
+const ATTR_NAME_PRESET: &str = "optarg_preset";
 const ATTR_NAME_METHOD: &str = "optarg_method";
 
 const INNER_SELF_VAR: &str = "_optarg_self";
@@ -107,6 +576,30 @@ const ERR_MSG_UNDERSCORE_ARG: &str = "(optarg2chain) `_` cannot be used for this
 const ERR_MSG_UNUSABLE_PAT: &str = "(optarg2chain) unusable pattern found";
 const ERR_MSG_UNSUPPORTED_FN_SIG: &str =
     "(optarg2chain) function or method with `unsafe`, `const` or `extern` is not supported";
+const ERR_MSG_NOT_OPTION_TYPE: &str = "(optarg2chain) `optarg_none` needs `Option<T>` type";
+const ERR_MSG_UNKNOWN_SETTER: &str =
+    "(optarg2chain) setter must be one of `into`, `exact` and `clone_from_ref`";
+const ERR_MSG_NOT_BOOL_TYPE: &str = "(optarg2chain) `optarg_flag` needs `bool` type";
+const ERR_MSG_NOT_STRUCT_TYPE: &str = "(optarg2chain) `optarg_flatten` needs a struct type";
+const ERR_MSG_NOT_ENUM_TYPE: &str = "(optarg2chain) `optarg_variants` needs an enum type";
+const ERR_MSG_ENV_WITHOUT_DEFAULT: &str = "(optarg2chain) `env` needs `default = expr`";
+const ERR_MSG_UNKNOWN_ENV_ERROR: &str =
+    "(optarg2chain) `env_error` must be `fallback`, `panic` or `error`";
+const ERR_MSG_ASYNC_DEFAULT_IN_SYNC_FN: &str =
+    "(optarg2chain) `async` default value needs an async function or method";
+const ERR_MSG_INTO_FUTURE_IN_SYNC_FN: &str =
+    "(optarg2chain) `into_future` needs an async function or method";
+const ERR_MSG_INTO_FUTURE_IMPL_TRAIT: &str =
+    "(optarg2chain) `into_future` cannot be used with `impl Trait` return type";
+const ERR_MSG_BOXED_FUTURE_IN_SYNC_FN: &str =
+    "(optarg2chain) `boxed_future` needs an async function or method";
+const ERR_MSG_BLOCKING_IN_SYNC_FN: &str =
+    "(optarg2chain) `blocking` needs an async function or method";
+const ERR_MSG_UNKNOWN_FUTURE_BOUND: &str = "(optarg2chain) `boxed_future` only accepts `?Send`";
+const ERR_MSG_RESET_ALL_CONFLICT: &str =
+    "(optarg2chain) optional argument `all` conflicts with `reset_all` method";
+const ERR_MSG_VARIANTS_WITHOUT_DEFAULT: &str =
+    "(optarg2chain) `optarg_variants` needs a default value given by `optarg` or `optarg_default`";
 
 /// Generates a builder struct and methods for the specified function.
 #[proc_macro_attribute]
@@ -114,13 +607,26 @@ pub fn optarg_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
     let FnAttr {
         builder_struct_name,
         terminal_method_name,
+        setter: default_setter,
+        in_place,
+        required_setters,
+        validate,
+        groups,
+        overridable,
+        into_future,
+        boxed_future,
+        blocking,
+        full,
     } = syn::parse_macro_input!(attr as FnAttr);
-    let item: syn::ItemFn = syn::parse_macro_input!(item);
+    let mut item: syn::ItemFn = syn::parse_macro_input!(item);
     if let Err(e) = check_sig(&item.sig) {
         return TokenStream::from(e.to_compile_error());
     }
+    let presets = match take_presets(&mut item.attrs) {
+        Ok(presets) => presets,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
     let return_type = &item.sig.output;
-    let struct_marker_type = generics::generate_type_holder(&item.sig.generics);
     let args: Vec<&syn::PatType> = item
         .sig
         .inputs
@@ -132,66 +638,248 @@ pub fn optarg_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
         .collect();
     let vis = &item.vis;
 
-    let args = parse_typed_args(&args);
-    let (impl_generics, ty_generics, where_clause) = item.sig.generics.split_for_impl();
-    let (arg_name, _, req_ident, req_ty, opt_ident, opt_ty, opt_default_value) =
-        separate_args(&args);
-    let func_attrs = &item.attrs;
-    let async_ = &item.sig.asyncness;
-    let await_ = if async_.is_some() {
-        Some(quote! { .await })
+    let args = match parse_typed_args(&args, default_setter) {
+        Ok(args) => args,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    let SeparatedArgs {
+        arg_name,
+        req_ident,
+        req_ty,
+        opt_args,
+        ..
+    } = separate_args(&args);
+    let (builder_generics, alloc_crate) =
+        lazy_generics(&item.sig.generics, &builder_struct_name, &opt_args);
+    let builder_generics = if overridable {
+        match overrides::static_generics(&builder_generics, &opt_args) {
+            Ok(generics) => generics,
+            Err(e) => return TokenStream::from(e.to_compile_error()),
+        }
     } else {
-        None
+        builder_generics
     };
+    let (impl_generics, ty_generics, where_clause) = builder_generics.split_for_impl();
+    let struct_marker_type = generics::generate_type_holder(&builder_generics);
+    if let Err(e) = check_arg_groups(&groups, &opt_args) {
+        return TokenStream::from(e.to_compile_error());
+    }
+    if let Err(e) = check_presets(&presets, &opt_args) {
+        return TokenStream::from(e.to_compile_error());
+    }
+    if let Err(e) = check_reset_all(&opt_args) {
+        return TokenStream::from(e.to_compile_error());
+    }
+    if let Err(e) = check_async_defaults(&item.sig, &opt_args) {
+        return TokenStream::from(e.to_compile_error());
+    }
+    if into_future {
+        if let Err(e) = check_into_future(&item.sig) {
+            return TokenStream::from(e.to_compile_error());
+        }
+    }
+    if boxed_future.is_some() && item.sig.asyncness.is_none() {
+        let err = Error::new(item.sig.ident.span(), ERR_MSG_BOXED_FUTURE_IN_SYNC_FN);
+        return TokenStream::from(err.to_compile_error());
+    }
+    if blocking && item.sig.asyncness.is_none() {
+        let err = Error::new(item.sig.ident.span(), ERR_MSG_BLOCKING_IN_SYNC_FN);
+        return TokenStream::from(err.to_compile_error());
+    }
+    let boxed_future = boxed_future.map(|send| terminal::BoxedFuture::new(send, &builder_generics));
+    let binding_order = match dependency::sort_by_dependency(&opt_args) {
+        Ok(binding_order) => binding_order,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    let func_attrs = &item.attrs;
 
     let mut inner_func = item.clone();
     erase_optarg_attr(&mut inner_func.sig);
-    inner_func.vis = syn::Visibility::Inherited;
     let func_name = inner_func.sig.ident.clone();
-    let inner_func_name = syn::Ident::new("_optarg_inner_func", Span::call_site());
+    let inner_func_name = if full {
+        format_ident!("{}_full", func_name)
+    } else {
+        syn::Ident::new("_optarg_inner_func", Span::call_site())
+    };
     inner_func.sig.ident = inner_func_name.clone();
+    if !opt_args.is_empty() {
+        let mut redirect = recursion::RedirectFullCalls {
+            ident: &func_name,
+            method: false,
+            arity: arg_name.len(),
+            method_call_arity: None,
+            target: &inner_func_name,
+            shadowed: false,
+        };
+        inner_func.block = Box::new(redirect.fold_block(*inner_func.block));
+    }
+    // The function taking all arguments is placed in the terminal method unless `full` is given
+    let (full_func, nested_inner_func) = if full {
+        // The document of the original function is given to the function returning the builder
+        inner_func.attrs.retain(|attr| !attr.path.is_ident("doc"));
+        inner_func.attrs.insert(0, doc::full_fn(&func_name));
+        (Some(inner_func), None)
+    } else {
+        inner_func.vis = syn::Visibility::Inherited;
+        (None, Some(inner_func))
+    };
     let doc::DocAttrs {
         doc_builder_struct,
-        doc_setter,
         doc_terminal_method,
-    } = doc::generate_doc(&func_name, &opt_ident);
+    } = doc::generate_doc(&func_name);
+    let error_kinds = terminal::ErrorKinds::new(&opt_args, validate.is_some(), &groups);
+    let error_ident = error_ident(&builder_struct_name, &error_kinds);
+    let error_type = error_ident.as_ref().map(|error_ident| {
+        terminal::generate_error_type(vis, error_ident, &func_name, &error_kinds)
+    });
+    let (opt_field_ident, opt_field_ty) = generate_opt_fields(
+        &opt_args,
+        error_ident.as_ref(),
+        &alloc_crate_ident(&builder_struct_name),
+    );
+    let setters = setter::generate_setters(
+        vis,
+        &opt_args,
+        error_ident.as_ref(),
+        &alloc_crate_ident(&builder_struct_name),
+    );
+    let variant_setters = variants::generate_variant_setters(vis, &opt_args);
+    let preset_methods = setter::generate_preset_methods(vis, &presets, &opt_args);
+    let default_items = terminal::generate_default_items(vis, &opt_args, &arg_name, where_clause);
+    let overrides = if overridable {
+        overrides::generate_overrides(vis, &opt_args)
+    } else {
+        vec![]
+    };
+    let reset_methods = setter::generate_reset_methods(vis, &opt_args, &opt_field_ident);
+    let required_setters = if required_setters {
+        setter::generate_required_setters(vis, &req_ident, &req_ty, default_setter, in_place)
+    } else {
+        vec![]
+    };
+    let in_place_items = if in_place {
+        let field_ident: Vec<&syn::Ident> =
+            req_ident.iter().copied().chain(&opt_field_ident).collect();
+        let field_ty: Vec<proc_macro2::TokenStream> = req_ty
+            .iter()
+            .map(|ty| quote! { #ty })
+            .chain(opt_field_ty.iter().cloned())
+            .collect();
+        let clone_impl = generate_clone_impl(
+            &builder_struct_name,
+            &builder_generics,
+            &field_ident,
+            &field_ty,
+        );
+        let in_place_setters = setter::generate_in_place_setters(vis, &opt_args);
+        let cloned_terminal = terminal::generate_cloned_terminal(
+            vis,
+            &item.sig.asyncness,
+            &terminal_method_name,
+            return_type,
+            where_clause,
+        );
+        Some((
+            clone_impl,
+            quote! { #(#in_place_setters)* #cloned_terminal },
+        ))
+    } else {
+        None
+    };
+    let (clone_impl, in_place_methods) = in_place_items.unzip();
+    let flatten_setters = flatten::generate_flatten_setters(vis, &opt_args);
+    let blocking_terminals = if blocking {
+        Some(terminal::generate_blocking_terminals(
+            vis,
+            &terminal_method_name,
+            return_type,
+            where_clause,
+            error_ident.as_ref(),
+        ))
+    } else {
+        None
+    };
+    let into_future_impl = if into_future {
+        Some(terminal::generate_into_future_impl(
+            &builder_struct_name,
+            &builder_generics,
+            &terminal_method_name,
+            return_type,
+            boxed_future.as_ref(),
+        ))
+    } else {
+        None
+    };
+    let terminal = terminal::generate_terminal(
+        &terminal::Terminal {
+            vis,
+            asyncness: &item.sig.asyncness,
+            ident: &terminal_method_name,
+            output: return_type,
+            where_clause,
+            doc: &doc_terminal_method,
+            error_ident: error_ident.as_ref(),
+            validate: validate.as_ref(),
+            groups: &groups,
+            overridable,
+            boxed_future: boxed_future.as_ref(),
+            arg_name: &arg_name,
+            prologue: quote! {
+                #nested_inner_func
+
+                #(
+                    let #req_ident: #req_ty = self.#req_ident;
+                )*
+            },
+            call: quote! {
+                #inner_func_name (
+                    #(
+                        #arg_name,
+                    )*
+                )
+            },
+        },
+        &binding_order,
+    );
 
     TokenStream::from(quote! {
+        #alloc_crate
+
         #doc_builder_struct
         #vis struct #builder_struct_name #ty_generics {
             #(#req_ident: #req_ty,)*
-            #(#opt_ident: core::option::Option<#opt_ty>,)*
+            #(#opt_field_ident: #opt_field_ty,)*
             _optarg_marker: #struct_marker_type
         }
 
+        #error_type
+
+        #clone_impl
+
+        #into_future_impl
+
         impl #impl_generics #builder_struct_name #ty_generics {
-            #(
-                #doc_setter
-                #vis fn #opt_ident<_OPTARG_VALUE: core::convert::Into<#opt_ty>>(
-                    mut self, value: _OPTARG_VALUE) -> Self {
-                    let value = <_OPTARG_VALUE as core::convert::Into<#opt_ty>>::into(value);
-                    self.#opt_ident = Some(value);
-                    self
-                }
-            )*
+            #(#default_items)*
 
-            #doc_terminal_method
-            #vis #async_ fn #terminal_method_name(self) #return_type #where_clause {
-                #inner_func
+            #(#overrides)*
 
-                #(
-                    let #req_ident: #req_ty = self.#req_ident;
-                )*
-                #(
-                    let #opt_ident: #opt_ty = self.#opt_ident.unwrap_or_else(|| { #opt_default_value });
-                )*
-                #inner_func_name (
-                    #(
-                        #arg_name,
-                    )*
-                )
-                #await_
-            }
+            #(#required_setters)*
+
+            #(#setters)*
+
+            #(#variant_setters)*
+
+            #(#flatten_setters)*
+
+            #(#preset_methods)*
+
+            #reset_methods
+
+            #in_place_methods
+
+            #terminal
+
+            #blocking_terminals
         }
 
         #(#func_attrs)*
@@ -205,11 +893,13 @@ pub fn optarg_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #req_ident,
                 )*
                 #(
-                    #opt_ident: core::option::Option::None,
+                    #opt_field_ident: core::option::Option::None,
                 )*
                 _optarg_marker: core::marker::PhantomData,
             }
         }
+
+        #full_func
     })
 }
 
@@ -270,41 +960,120 @@ pub fn optarg_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Generates a macro to pass the fields of the struct to `optarg_fn` and `optarg_method`.
+/// Use `#[optarg_flatten]` for an argument of the struct type to generate a setter for each field.
+#[proc_macro_derive(OptargFlatten)]
+pub fn derive_optarg_flatten(item: TokenStream) -> TokenStream {
+    let item: syn::DeriveInput = syn::parse_macro_input!(item);
+    match flatten::derive(&item) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
+
+/// Generates a macro to pass the variants of the fieldless enum to `optarg_fn` and `optarg_method`.
+/// Use `#[optarg_variants]` for an argument of the enum type to generate a setter for each variant.
+#[proc_macro_derive(OptargVariants)]
+pub fn derive_optarg_variants(item: TokenStream) -> TokenStream {
+    let item: syn::DeriveInput = syn::parse_macro_input!(item);
+    match variants::derive(&item) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __optarg_flatten_setters(input: TokenStream) -> TokenStream {
+    let input: flatten::FlattenSetters = syn::parse_macro_input!(input);
+    TokenStream::from(flatten::generate_setters(&input))
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __optarg_variant_setters(input: TokenStream) -> TokenStream {
+    let input: variants::VariantSetters = syn::parse_macro_input!(input);
+    TokenStream::from(variants::generate_setters(&input))
+}
+
 fn optarg_method(
-    input: syn::ImplItemMethod,
+    mut input: syn::ImplItemMethod,
     impl_original_generics: &syn::Generics,
     self_ty: &syn::Type,
-) -> Result<(Vec<syn::ImplItem>, syn::ItemStruct, syn::ItemImpl)> {
+) -> Result<(
+    Vec<syn::ImplItem>,
+    syn::ItemStruct,
+    proc_macro2::TokenStream,
+)> {
     check_sig(&input.sig)?;
+    let presets = take_presets(&mut input.attrs)?;
     let (optarg_attrs, other_attrs) = separate_attrs(&input.attrs);
     let FnAttr {
         builder_struct_name,
         terminal_method_name,
-    } = optarg_attrs[0].parse_args().unwrap();
+        setter: default_setter,
+        in_place,
+        required_setters,
+        validate,
+        groups,
+        overridable,
+        into_future,
+        boxed_future,
+        blocking,
+        full,
+    } = optarg_attrs[0].parse_args()?;
     let vis = input.vis;
     let mut self_replace = SelfReplace(self_ty);
     let return_type = self_replace.fold_return_type(input.sig.output.clone());
     let method_name = &input.sig.ident;
     let merged_generics = merge_generics(impl_original_generics, &input.sig, self_ty);
-    let (impl_generics, ty_generics, where_clause) = merged_generics.split_for_impl();
     let (original_receiver, receiver_ident, receiver_ty, args) =
         separate_receiver(&input.sig, self_ty)?;
-    let struct_marker_type = generics::generate_type_holder(&merged_generics);
 
     let replaced_args: Vec<syn::PatType> = args
         .iter()
         .map(|pt| self_replace.fold_pat_type((*pt).clone()))
         .collect();
-    let args: Vec<&syn::PatType> = replaced_args.iter().map(|pt| pt).collect();
-    let args = parse_typed_args(&args);
-    let (arg_name, arg_ty, req_ident, req_ty, opt_ident, opt_ty, opt_default_value) =
-        separate_args(&args);
-    let async_ = &input.sig.asyncness;
-    let await_ = if async_.is_some() {
-        Some(quote! { .await })
+    let args: Vec<&syn::PatType> = replaced_args.iter().collect();
+    let args = parse_typed_args(&args, default_setter)?;
+    let SeparatedArgs {
+        arg_name,
+        arg_ty,
+        req_ident,
+        req_ty,
+        opt_args,
+    } = separate_args(&args);
+    let (merged_generics, alloc_crate) =
+        lazy_generics(&merged_generics, &builder_struct_name, &opt_args);
+    let merged_generics = if overridable {
+        overrides::static_generics(&merged_generics, &opt_args)?
     } else {
-        None
+        merged_generics
     };
+    let (impl_generics, ty_generics, where_clause) = merged_generics.split_for_impl();
+    let struct_marker_type = generics::generate_type_holder(&merged_generics);
+    check_arg_groups(&groups, &opt_args)?;
+    check_presets(&presets, &opt_args)?;
+    check_reset_all(&opt_args)?;
+    check_async_defaults(&input.sig, &opt_args)?;
+    if into_future {
+        check_into_future(&input.sig)?;
+    }
+    if boxed_future.is_some() && input.sig.asyncness.is_none() {
+        return Err(Error::new(
+            input.sig.ident.span(),
+            ERR_MSG_BOXED_FUTURE_IN_SYNC_FN,
+        ));
+    }
+    if blocking && input.sig.asyncness.is_none() {
+        return Err(Error::new(
+            input.sig.ident.span(),
+            ERR_MSG_BLOCKING_IN_SYNC_FN,
+        ));
+    }
+    let boxed_future = boxed_future.map(|send| terminal::BoxedFuture::new(send, &merged_generics));
+    let binding_order = dependency::sort_by_dependency(&opt_args)?;
+    let async_ = &input.sig.asyncness;
 
     let insert_self = if receiver_ident.is_empty() {
         vec![]
@@ -312,19 +1081,139 @@ fn optarg_method(
         vec![quote! { #(#receiver_ident: self)* }]
     };
 
-    let inner_method_ident = syn::Ident::new(
-        &format!("_optarg_inner_{}", method_name),
-        method_name.span(),
-    );
-    let inner_method_block = &input.block;
+    let inner_method_ident = if full {
+        format_ident!("{}_full", method_name)
+    } else {
+        syn::Ident::new(
+            &format!("_optarg_inner_{}", method_name),
+            method_name.span(),
+        )
+    };
+    let inner_method_block = if opt_args.is_empty() {
+        input.block.clone()
+    } else {
+        let has_receiver = !original_receiver.is_empty();
+        let mut redirect = recursion::RedirectFullCalls {
+            ident: method_name,
+            method: true,
+            arity: arg_name.len() + has_receiver as usize,
+            method_call_arity: if has_receiver {
+                Some(arg_name.len())
+            } else {
+                None
+            },
+            target: &inner_method_ident,
+            shadowed: false,
+        };
+        redirect.fold_block(input.block.clone())
+    };
+    // `_full` method keeps the attributes of the original method except its document
+    let (full_vis, full_attrs) = if full {
+        let attrs: Vec<&syn::Attribute> = other_attrs
+            .iter()
+            .copied()
+            .filter(|attr| !attr.path.is_ident("doc"))
+            .collect();
+        let doc_full_method = doc::full_fn(method_name);
+        (vis.clone(), Some(quote! { #doc_full_method #(#attrs)* }))
+    } else {
+        (syn::Visibility::Inherited, None)
+    };
     let doc::DocAttrs {
         doc_builder_struct,
-        doc_setter,
         doc_terminal_method,
-    } = doc::generate_doc(&method_name, &opt_ident);
+    } = doc::generate_doc(method_name);
+    let error_kinds = terminal::ErrorKinds::new(&opt_args, validate.is_some(), &groups);
+    let error_ident = error_ident(&builder_struct_name, &error_kinds);
+    let error_type = error_ident.as_ref().map(|error_ident| {
+        terminal::generate_error_type(&vis, error_ident, method_name, &error_kinds)
+    });
+    let (opt_field_ident, opt_field_ty) = generate_opt_fields(
+        &opt_args,
+        error_ident.as_ref(),
+        &alloc_crate_ident(&builder_struct_name),
+    );
+    let setters = setter::generate_setters(
+        &vis,
+        &opt_args,
+        error_ident.as_ref(),
+        &alloc_crate_ident(&builder_struct_name),
+    );
+    let variant_setters = variants::generate_variant_setters(&vis, &opt_args);
+    let preset_methods = setter::generate_preset_methods(&vis, &presets, &opt_args);
+    let default_items = terminal::generate_default_items(&vis, &opt_args, &arg_name, where_clause);
+    let overrides = if overridable {
+        overrides::generate_overrides(&vis, &opt_args)
+    } else {
+        vec![]
+    };
+    let reset_methods = setter::generate_reset_methods(&vis, &opt_args, &opt_field_ident);
+    let required_setters = if required_setters {
+        setter::generate_required_setters(&vis, &req_ident, &req_ty, default_setter, in_place)
+    } else {
+        vec![]
+    };
+    let in_place_items = if in_place {
+        let field_ident: Vec<&syn::Ident> = receiver_ident
+            .iter()
+            .chain(req_ident.iter().copied())
+            .chain(&opt_field_ident)
+            .collect();
+        let field_ty: Vec<proc_macro2::TokenStream> = receiver_ty
+            .iter()
+            .map(|ty| quote! { #ty })
+            .chain(req_ty.iter().map(|ty| quote! { #ty }))
+            .chain(opt_field_ty.iter().cloned())
+            .collect();
+        let clone_impl = generate_clone_impl(
+            &builder_struct_name,
+            &merged_generics,
+            &field_ident,
+            &field_ty,
+        );
+        let in_place_setters = setter::generate_in_place_setters(&vis, &opt_args);
+        let cloned_terminal = terminal::generate_cloned_terminal(
+            &vis,
+            async_,
+            &terminal_method_name,
+            &return_type,
+            where_clause,
+        );
+        Some((
+            clone_impl,
+            quote! { #(#in_place_setters)* #cloned_terminal },
+        ))
+    } else {
+        None
+    };
+    let (clone_impl, in_place_methods) = in_place_items.unzip();
+    let flatten_setters = flatten::generate_flatten_setters(&vis, &opt_args);
+    let blocking_terminals = if blocking {
+        Some(terminal::generate_blocking_terminals(
+            &vis,
+            &terminal_method_name,
+            &return_type,
+            where_clause,
+            error_ident.as_ref(),
+        ))
+    } else {
+        None
+    };
+    let into_future_impl = if into_future {
+        Some(terminal::generate_into_future_impl(
+            &builder_struct_name,
+            &merged_generics,
+            &terminal_method_name,
+            &return_type,
+            boxed_future.as_ref(),
+        ))
+    } else {
+        None
+    };
 
     let mut inner_method: syn::ImplItemMethod = syn::parse_quote! {
-        #async_ fn #inner_method_ident (
+        #full_attrs
+        #full_vis #async_ fn #inner_method_ident (
             #(#original_receiver,)*
             #(#arg_name: #arg_ty,)*) #return_type #where_clause #inner_method_block
     };
@@ -336,7 +1225,7 @@ fn optarg_method(
         #vis struct #builder_struct_name #ty_generics {
             #(#receiver_ident: #receiver_ty,)*
             #(#req_ident: #req_ty,)*
-            #(#opt_ident: core::option::Option<#opt_ty>,)*
+            #(#opt_field_ident: #opt_field_ty,)*
             _optarg_marker: #struct_marker_type,
         }
     };
@@ -353,43 +1242,80 @@ fn optarg_method(
                     #req_ident,
                 )*
                 #(
-                    #opt_ident: core::option::Option::None,
+                    #opt_field_ident: core::option::Option::None,
                 )*
                 _optarg_marker: core::marker::PhantomData,
             }
         }
     };
     new_method.sig.generics = input.sig.generics.clone();
+    if alloc_crate.is_some() {
+        generics::add_lazy_lifetime(&mut new_method.sig.generics);
+    }
     let new_method: syn::ImplItem = new_method.into();
 
     let self_ty_no_generics = erase_generics(self_ty);
 
-    let struct_impl: syn::ItemImpl = syn::parse_quote! {
-        impl #impl_generics #builder_struct_name #ty_generics {
-            #(
-                #doc_setter
-                #vis fn #opt_ident<_OPTARG_VALUE: core::convert::Into<#opt_ty>>(
-                    mut self, value: _OPTARG_VALUE) -> Self {
-                    let value = <_OPTARG_VALUE as core::convert::Into<#opt_ty>>::into(value);
-                    self.#opt_ident = Some(value);
-                    self
-                }
-            )*
-
-            #doc_terminal_method
-            #vis #async_ fn #terminal_method_name(self) #return_type #where_clause {
+    let terminal = terminal::generate_terminal(
+        &terminal::Terminal {
+            vis: &vis,
+            asyncness: async_,
+            ident: &terminal_method_name,
+            output: &return_type,
+            where_clause,
+            doc: &doc_terminal_method,
+            error_ident: error_ident.as_ref(),
+            validate: validate.as_ref(),
+            groups: &groups,
+            overridable,
+            boxed_future: boxed_future.as_ref(),
+            arg_name: &arg_name,
+            prologue: quote! {
                 #(
                     let #receiver_ident: #receiver_ty = self.#receiver_ident;
                 )*
                 #(
                     let #req_ident: #req_ty = self.#req_ident;
                 )*
-                #(
-                    let #opt_ident: #opt_ty = self.#opt_ident.unwrap_or_else(|| { #opt_default_value });
-                )*
+            },
+            call: quote! {
                 #self_ty_no_generics::#inner_method_ident( #(#receiver_ident,)* #(#arg_name, )* )
-                #await_
-            }
+            },
+        },
+        &binding_order,
+    );
+
+    let struct_impl = quote! {
+        #alloc_crate
+
+        #error_type
+
+        #clone_impl
+
+        #into_future_impl
+
+        impl #impl_generics #builder_struct_name #ty_generics {
+            #(#default_items)*
+
+            #(#overrides)*
+
+            #(#required_setters)*
+
+            #(#setters)*
+
+            #(#variant_setters)*
+
+            #(#flatten_setters)*
+
+            #(#preset_methods)*
+
+            #reset_methods
+
+            #in_place_methods
+
+            #terminal
+
+            #blocking_terminals
         }
     };
 
@@ -399,12 +1325,284 @@ fn optarg_method(
 struct Arg<'a> {
     ident: &'a syn::Ident,
     ty: &'a syn::Type,
-    default_value: Option<syn::Expr>,
+    opt: Option<OptArg>,
+}
+
+struct OptArg {
+    default_value: syn::Expr,
+    kind: OptArgKind,
+    /// Generates `try_` prefixed setter with `TryInto`
+    try_into: bool,
+    setter: SetterFlavor,
+    /// Generates `_with` suffixed setter taking a closure
+    lazy: bool,
+    /// Path of the macro generated by `OptargVariants` to generate setters for each variant
+    variants: Option<syn::Path>,
+    /// Function to validate the value, called by the terminal method
+    validate: Option<syn::Path>,
+    /// Range which the value must be contained in
+    range: Option<syn::Expr>,
+    /// Default value is given by `const = expr` and exposed as an associated constant
+    const_default: bool,
+    /// Default value is read from the environment variable if it is set
+    env: Option<EnvDefault>,
+    /// Default value is given by `async expr` and awaited by the terminal method
+    async_default: bool,
+}
+
+enum OptArgKind {
+    /// Argument given by `optarg` or `optarg_default`
+    Value,
+    /// `Option<T>` argument given by `optarg_none`. Holds the inner type `T`.
+    None(Box<syn::Type>),
+    /// `bool` argument given by `optarg_flag`. Holds the default value.
+    Flag(bool),
+    /// Collection argument given by `optarg_collect`
+    Collect,
+    /// Struct argument given by `optarg_flatten`. Holds the path of the macro generated by `OptargFlatten`.
+    Flatten(Box<syn::Path>),
+    /// Argument built by another builder given by `optarg_nested`
+    Nested(Box<NestedArgAttr>),
+}
+
+// Arguments of `optarg_nested` attribute
+struct NestedArgAttr {
+    /// Expression to create the nested builder
+    ctor: syn::Expr,
+    builder_ty: syn::Type,
+    terminal_method_name: syn::Ident,
+}
+
+impl Parse for NestedArgAttr {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let ctor = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let builder_ty = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let terminal_method_name = input.parse()?;
+        input.parse::<Option<syn::Token![,]>>()?;
+        Ok(NestedArgAttr {
+            ctor,
+            builder_ty,
+            terminal_method_name,
+        })
+    }
 }
 
 struct FnAttr {
     builder_struct_name: syn::Ident,
     terminal_method_name: syn::Ident,
+    setter: SetterFlavor,
+    /// Generates setters taking `&mut self`
+    in_place: bool,
+    /// Generates setters for required arguments
+    required_setters: bool,
+    /// Function to validate all arguments, called by the terminal method
+    validate: Option<syn::Path>,
+    groups: ArgGroups,
+    /// Generates functions to override default values in a scope
+    overridable: bool,
+    /// Implements `IntoFuture` for the builder of an async function
+    into_future: bool,
+    /// Async terminal methods return boxed futures. Holds whether the futures are `Send`.
+    boxed_future: Option<bool>,
+    /// Generates `_blocking` suffixed terminal methods for an async function
+    blocking: bool,
+    /// Generates `_full` suffixed function taking all arguments
+    full: bool,
+}
+
+/// Groups of optional arguments checked by the terminal method
+#[derive(Default)]
+struct ArgGroups {
+    /// Groups given by `conflicts(a, b, ..)`. Only one of the arguments in a group can be set.
+    conflicts: Vec<Vec<syn::Ident>>,
+    /// Pairs given by `requires(a => b)`. `b` needs to be set if `a` is set.
+    requires: Vec<Requirement>,
+}
+
+struct Requirement {
+    arg: syn::Ident,
+    required: syn::Ident,
+}
+
+impl Parse for Requirement {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let arg = input.parse()?;
+        input.parse::<syn::Token![=>]>()?;
+        let required = input.parse()?;
+        Ok(Requirement { arg, required })
+    }
+}
+
+// Arguments of `optarg_preset` attribute
+struct Preset {
+    /// Name of the generated method
+    ident: syn::Ident,
+    /// Pairs of the optional argument name and the value
+    values: Vec<(syn::Ident, syn::Expr)>,
+}
+
+impl Parse for Preset {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let ident = input.parse()?;
+        let mut values = vec![];
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let arg = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            values.push((arg, input.parse()?));
+        }
+        Ok(Preset { ident, values })
+    }
+}
+
+// Arguments of `optarg` attribute
+struct OptArgAttr {
+    default_value: syn::Expr,
+    try_into: bool,
+    setter: Option<SetterFlavor>,
+    lazy: bool,
+    validate: Option<syn::Path>,
+    range: Option<syn::Expr>,
+    const_default: bool,
+    env: Option<EnvDefault>,
+    async_default: bool,
+}
+
+impl Parse for OptArgAttr {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let const_default = input.peek(syn::Token![const]);
+        let async_default = input.peek(syn::Token![async]);
+        let mut env = None;
+        let mut default_value = None;
+        let env_ident = if const_default {
+            input.parse::<syn::Token![const]>()?;
+            input.parse::<syn::Token![=]>()?;
+            default_value = Some(input.parse()?);
+            None
+        } else if async_default {
+            // An async block is awaited as it is
+            if !is_async_block(input) {
+                input.parse::<syn::Token![async]>()?;
+            }
+            default_value = Some(input.parse()?);
+            None
+        } else if is_env_default(input) {
+            let option: syn::Ident = input.parse()?;
+            check_std_feature(&option)?;
+            input.parse::<syn::Token![=]>()?;
+            env = Some(EnvDefault {
+                var: input.parse()?,
+                on_error: EnvErrorPolicy::Fallback,
+            });
+            Some(option)
+        } else {
+            default_value = Some(input.parse()?);
+            None
+        };
+        let mut try_into = false;
+        let mut setter = None;
+        let mut lazy = false;
+        let mut validate = None;
+        let mut range = None;
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option: syn::Ident = input.parse()?;
+            if option == "try_into" {
+                try_into = true;
+            } else if option == "setter" {
+                input.parse::<syn::Token![=]>()?;
+                setter = Some(input.parse()?);
+            } else if option == "lazy" {
+                lazy = true;
+            } else if option == "validate" {
+                input.parse::<syn::Token![=]>()?;
+                validate = Some(input.parse()?);
+            } else if option == "range" {
+                input.parse::<syn::Token![=]>()?;
+                range = Some(input.parse()?);
+            } else if option == "default" && env.is_some() {
+                input.parse::<syn::Token![=]>()?;
+                default_value = Some(input.parse()?);
+            } else if option == "env_error" {
+                let env = env.as_mut().ok_or_else(|| unknown_option(&option))?;
+                input.parse::<syn::Token![=]>()?;
+                env.on_error = input.parse()?;
+            } else {
+                return Err(unknown_option(&option));
+            }
+        }
+        let default_value = match (default_value, env_ident) {
+            (Some(default_value), _) => default_value,
+            (None, Some(env_ident)) => {
+                return Err(Error::new(env_ident.span(), ERR_MSG_ENV_WITHOUT_DEFAULT))
+            }
+            (None, None) => unreachable!(),
+        };
+        Ok(OptArgAttr {
+            default_value,
+            try_into,
+            setter,
+            lazy,
+            validate,
+            range,
+            const_default,
+            env,
+            async_default,
+        })
+    }
+}
+
+// Returns true if the input starts with an async block
+fn is_async_block(input: ParseStream) -> bool {
+    input.peek(syn::Token![async])
+        && (input.peek2(syn::token::Brace) || input.peek2(syn::Token![move]))
+}
+
+// Returns true if the input starts with `env = `
+fn is_env_default(input: ParseStream) -> bool {
+    let fork = input.fork();
+    matches!(fork.parse::<syn::Ident>(), Ok(ident) if ident == "env")
+        && fork.peek(syn::Token![=])
+        && !fork.peek(syn::Token![==])
+}
+
+/// Default value read from an environment variable, given by `env = "VAR"`
+struct EnvDefault {
+    var: syn::LitStr,
+    on_error: EnvErrorPolicy,
+}
+
+/// Behavior when the environment variable cannot be parsed
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EnvErrorPolicy {
+    /// Uses the default value
+    Fallback,
+    Panic,
+    /// Returns an error from `try_` prefixed terminal method
+    Error,
+}
+
+impl Parse for EnvErrorPolicy {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let policy: syn::Ident = input.parse()?;
+        if policy == "fallback" {
+            Ok(EnvErrorPolicy::Fallback)
+        } else if policy == "panic" {
+            Ok(EnvErrorPolicy::Panic)
+        } else if policy == "error" {
+            Ok(EnvErrorPolicy::Error)
+        } else {
+            Err(Error::new(policy.span(), ERR_MSG_UNKNOWN_ENV_ERROR))
+        }
+    }
 }
 
 impl Parse for FnAttr {
@@ -412,108 +1610,591 @@ impl Parse for FnAttr {
         let builder_struct_name: syn::Ident = input.parse()?;
         input.parse::<syn::Token![,]>()?;
         let terminal_method_name: syn::Ident = input.parse()?;
-        Ok(FnAttr {
+        let mut attr = FnAttr {
             builder_struct_name,
             terminal_method_name,
-        })
+            setter: SetterFlavor::Into,
+            in_place: false,
+            required_setters: false,
+            validate: None,
+            groups: ArgGroups::default(),
+            overridable: false,
+            into_future: false,
+            boxed_future: None,
+            blocking: false,
+            full: false,
+        };
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option: syn::Ident = input.parse()?;
+            if option == "setter" {
+                input.parse::<syn::Token![=]>()?;
+                attr.setter = input.parse()?;
+            } else if option == "in_place" {
+                attr.in_place = true;
+            } else if option == "required_setters" {
+                attr.required_setters = true;
+            } else if option == "validate" {
+                input.parse::<syn::Token![=]>()?;
+                attr.validate = Some(input.parse()?);
+            } else if option == "overridable" {
+                check_std_feature(&option)?;
+                attr.overridable = true;
+            } else if option == "into_future" {
+                check_std_feature(&option)?;
+                attr.into_future = true;
+            } else if option == "full" {
+                attr.full = true;
+            } else if option == "blocking" {
+                check_std_feature(&option)?;
+                attr.blocking = true;
+            } else if option == "boxed_future" {
+                check_std_feature(&option)?;
+                attr.boxed_future = Some(if input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    content.parse::<syn::Token![?]>()?;
+                    let bound: syn::Ident = content.parse()?;
+                    if bound != "Send" {
+                        return Err(Error::new(bound.span(), ERR_MSG_UNKNOWN_FUTURE_BOUND));
+                    }
+                    false
+                } else {
+                    true
+                });
+            } else if option == "conflicts" {
+                let content;
+                syn::parenthesized!(content in input);
+                let args = content.parse_terminated::<_, syn::Token![,]>(syn::Ident::parse)?;
+                attr.groups.conflicts.push(args.into_iter().collect());
+            } else if option == "requires" {
+                let content;
+                syn::parenthesized!(content in input);
+                let requires = content.parse_terminated::<_, syn::Token![,]>(Requirement::parse)?;
+                attr.groups.requires.extend(requires);
+            } else {
+                return Err(unknown_option(&option));
+            }
+        }
+        Ok(attr)
+    }
+}
+
+/// Parameter type of setters
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetterFlavor {
+    /// Takes a value converted by `Into`
+    Into,
+    /// Takes a value of the argument type
+    Exact,
+    /// Takes a reference and clones it
+    CloneFromRef,
+}
+
+impl Parse for SetterFlavor {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let flavor: syn::Ident = input.parse()?;
+        if flavor == "into" {
+            Ok(SetterFlavor::Into)
+        } else if flavor == "exact" {
+            Ok(SetterFlavor::Exact)
+        } else if flavor == "clone_from_ref" {
+            Ok(SetterFlavor::CloneFromRef)
+        } else {
+            Err(Error::new(flavor.span(), ERR_MSG_UNKNOWN_SETTER))
+        }
+    }
+}
+
+// Passes the flavor to the macros generated by derives in the same syntax as the option
+impl quote::ToTokens for SetterFlavor {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let flavor = match self {
+            SetterFlavor::Into => "into",
+            SetterFlavor::Exact => "exact",
+            SetterFlavor::CloneFromRef => "clone_from_ref",
+        };
+        tokens.extend(Some(proc_macro2::TokenTree::Ident(syn::Ident::new(
+            flavor,
+            Span::call_site(),
+        ))));
+    }
+}
+
+// Returns error if the option is used without `std` feature
+fn check_std_feature(option: &syn::Ident) -> Result<()> {
+    if cfg!(feature = "std") {
+        Ok(())
+    } else {
+        Err(Error::new(
+            option.span(),
+            format!("(optarg2chain) `{}` needs `std` feature", option),
+        ))
     }
 }
 
-fn parse_typed_args<'a>(args: &[&'a syn::PatType]) -> Vec<Arg<'a>> {
+fn unknown_option(option: &syn::Ident) -> Error {
+    Error::new(
+        option.span(),
+        format!("(optarg2chain) unknown option `{}`", option),
+    )
+}
+
+fn parse_typed_args<'a>(
+    args: &[&'a syn::PatType],
+    default_setter: SetterFlavor,
+) -> Result<Vec<Arg<'a>>> {
     args.iter()
         .map(|arg: &&syn::PatType| {
             let ident: &syn::Ident = match &*arg.pat {
                 syn::Pat::Ident(ident) => &ident.ident,
                 _ => panic!(),
             };
-            let ty: &syn::Type = &*arg.ty;
-            let default_value = parse_arg_attr(&arg.attrs, ty);
-            Arg {
-                ident,
-                ty,
-                default_value,
+            let ty: &syn::Type = &arg.ty;
+            let mut opt = parse_arg_attr(&arg.attrs, ty, default_setter)?;
+            if let Some(attr) = arg
+                .attrs
+                .iter()
+                .find(|attr| attr.path.is_ident(ATTR_NAME_VARIANTS_ARG))
+            {
+                let opt = opt
+                    .as_mut()
+                    .filter(|opt| matches!(opt.kind, OptArgKind::Value))
+                    .ok_or_else(|| Error::new(attr.span(), ERR_MSG_VARIANTS_WITHOUT_DEFAULT))?;
+                let path = variants::callback_macro_path(ty)
+                    .ok_or_else(|| Error::new(ty.span(), ERR_MSG_NOT_ENUM_TYPE))?;
+                opt.variants = Some(path);
             }
+            Ok(Arg { ident, ty, opt })
         })
         .collect()
 }
 
-fn parse_arg_attr(attrs: &[syn::Attribute], ty: &syn::Type) -> Option<syn::Expr> {
+fn parse_arg_attr(
+    attrs: &[syn::Attribute],
+    ty: &syn::Type,
+    default_setter: SetterFlavor,
+) -> Result<Option<OptArg>> {
     for attr in attrs {
         assert_eq!(attr.style, syn::AttrStyle::Outer);
 
         if attr.path.is_ident(ATTR_NAME_OPT_ARG) {
-            return Some(attr.parse_args().unwrap());
+            let OptArgAttr {
+                default_value,
+                try_into,
+                setter,
+                lazy,
+                validate,
+                range,
+                const_default,
+                env,
+                async_default,
+            } = attr.parse_args()?;
+            return Ok(Some(OptArg {
+                default_value,
+                kind: OptArgKind::Value,
+                try_into,
+                setter: setter.unwrap_or(default_setter),
+                lazy,
+                variants: None,
+                validate,
+                range,
+                const_default,
+                env,
+                async_default,
+            }));
         } else if attr.path.is_ident(ATTR_NAME_DEFAULT_ARG) {
             assert!(attr.tokens.is_empty());
-            return Some(syn::parse_quote! {
-                <#ty as core::default::Default>::default()
-            });
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! {
+                    <#ty as core::default::Default>::default()
+                },
+                kind: OptArgKind::Value,
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
+        } else if attr.path.is_ident(ATTR_NAME_NONE_ARG) {
+            assert!(attr.tokens.is_empty());
+            let inner_ty = option_inner_type(ty)
+                .ok_or_else(|| Error::new(ty.span(), ERR_MSG_NOT_OPTION_TYPE))?;
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! { core::option::Option::None },
+                kind: OptArgKind::None(Box::new(inner_ty.clone())),
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
+        } else if attr.path.is_ident(ATTR_NAME_COLLECT_ARG) {
+            assert!(attr.tokens.is_empty());
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! {
+                    <#ty as core::default::Default>::default()
+                },
+                kind: OptArgKind::Collect,
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
+        } else if attr.path.is_ident(ATTR_NAME_FLATTEN_ARG) {
+            assert!(attr.tokens.is_empty());
+            let macro_path = flatten::callback_macro_path(ty)
+                .ok_or_else(|| Error::new(ty.span(), ERR_MSG_NOT_STRUCT_TYPE))?;
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! {
+                    <#ty as core::default::Default>::default()
+                },
+                kind: OptArgKind::Flatten(Box::new(macro_path)),
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
+        } else if attr.path.is_ident(ATTR_NAME_NESTED_ARG) {
+            let nested: NestedArgAttr = attr.parse_args()?;
+            let ctor = &nested.ctor;
+            let terminal_method_name = &nested.terminal_method_name;
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! { #ctor.#terminal_method_name() },
+                kind: OptArgKind::Nested(Box::new(nested)),
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
+        } else if attr.path.is_ident(ATTR_NAME_FLAG_ARG) {
+            if !is_bool_type(ty) {
+                return Err(Error::new(ty.span(), ERR_MSG_NOT_BOOL_TYPE));
+            }
+            let default = if attr.tokens.is_empty() {
+                syn::LitBool {
+                    value: false,
+                    span: attr.span(),
+                }
+            } else {
+                attr.parse_args()?
+            };
+            return Ok(Some(OptArg {
+                default_value: syn::parse_quote! { #default },
+                kind: OptArgKind::Flag(default.value),
+                try_into: false,
+                setter: default_setter,
+                lazy: false,
+                variants: None,
+                validate: None,
+                range: None,
+                const_default: false,
+                env: None,
+                async_default: false,
+            }));
         } else {
             continue;
         }
     }
-    None
-}
-
-// separate args to (arg name, required ident, ty, optional ident, ty, defalut_value)
-fn separate_args<'a>(
-    args: &'a [Arg<'a>],
-) -> (
-    Vec<&'a syn::Ident>,
-    Vec<&'a syn::Type>,
-    Vec<&'a syn::Ident>,
-    Vec<&'a syn::Type>,
-    Vec<&'a syn::Ident>,
-    Vec<&'a syn::Type>,
-    Vec<&'a syn::Expr>,
-) {
-    let mut arg_name = vec![];
-    let mut arg_ty = vec![];
-    let mut req_ident = vec![];
-    let mut req_ty = vec![];
-    let mut opt_ident = vec![];
-    let mut opt_ty = vec![];
-    let mut opt_default_value = vec![];
+    Ok(None)
+}
+
+// Returns `T` if the given type is `Option<T>`
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_bool_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.is_ident("bool"),
+        _ => false,
+    }
+}
+
+struct SeparatedArgs<'a> {
+    arg_name: Vec<&'a syn::Ident>,
+    arg_ty: Vec<&'a syn::Type>,
+    req_ident: Vec<&'a syn::Ident>,
+    req_ty: Vec<&'a syn::Type>,
+    opt_args: Vec<&'a Arg<'a>>,
+}
+
+// separate args to required args and optional args
+fn separate_args<'a>(args: &'a [Arg<'a>]) -> SeparatedArgs<'a> {
+    let mut separated = SeparatedArgs {
+        arg_name: vec![],
+        arg_ty: vec![],
+        req_ident: vec![],
+        req_ty: vec![],
+        opt_args: vec![],
+    };
     for arg in args {
-        if arg.default_value.is_none() {
-            req_ident.push(arg.ident);
-            req_ty.push(arg.ty);
+        if arg.opt.is_some() {
+            separated.opt_args.push(arg);
         } else {
-            opt_ident.push(arg.ident);
-            opt_ty.push(arg.ty);
-            opt_default_value.push(arg.default_value.as_ref().unwrap());
+            separated.req_ident.push(arg.ident);
+            separated.req_ty.push(arg.ty);
+        }
+        separated.arg_name.push(arg.ident);
+        separated.arg_ty.push(arg.ty);
+    }
+    separated
+}
+
+// Returns error if an argument in the groups is not an optional argument
+fn check_arg_groups(groups: &ArgGroups, opt_args: &[&Arg]) -> Result<()> {
+    let names = groups.conflicts.iter().flatten().chain(
+        groups
+            .requires
+            .iter()
+            .flat_map(|requirement| vec![&requirement.arg, &requirement.required]),
+    );
+    check_opt_arg_names(names, opt_args)
+}
+
+// Returns error if an argument in the presets is not an optional argument
+fn check_presets(presets: &[Preset], opt_args: &[&Arg]) -> Result<()> {
+    let names = presets
+        .iter()
+        .flat_map(|preset| preset.values.iter().map(|(ident, _)| ident));
+    check_opt_arg_names(names, opt_args)
+}
+
+// Returns error if the reset method of an optional argument collides with `reset_all`
+fn check_reset_all(opt_args: &[&Arg]) -> Result<()> {
+    match opt_args.iter().find(|arg| arg.ident == "all") {
+        Some(arg) => Err(Error::new(arg.ident.span(), ERR_MSG_RESET_ALL_CONFLICT)),
+        None => Ok(()),
+    }
+}
+
+// Returns error if `async` default values are given to a non-async function
+fn check_async_defaults(sig: &syn::Signature, opt_args: &[&Arg]) -> Result<()> {
+    if sig.asyncness.is_some() {
+        return Ok(());
+    }
+    match opt_args
+        .iter()
+        .find(|arg| arg.opt.as_ref().unwrap().async_default)
+    {
+        Some(arg) => Err(Error::new(
+            arg.ident.span(),
+            ERR_MSG_ASYNC_DEFAULT_IN_SYNC_FN,
+        )),
+        None => Ok(()),
+    }
+}
+
+// Returns error if `IntoFuture` cannot be implemented for the builder of the function
+fn check_into_future(sig: &syn::Signature) -> Result<()> {
+    if sig.asyncness.is_none() {
+        return Err(Error::new(sig.ident.span(), ERR_MSG_INTO_FUTURE_IN_SYNC_FN));
+    }
+    if let syn::ReturnType::Type(_, ty) = &sig.output {
+        if contains_impl_trait(quote! { #ty }) {
+            return Err(Error::new(ty.span(), ERR_MSG_INTO_FUTURE_IMPL_TRAIT));
+        }
+    }
+    Ok(())
+}
+
+fn contains_impl_trait(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
+}
+
+fn check_opt_arg_names<'a>(
+    names: impl Iterator<Item = &'a syn::Ident>,
+    opt_args: &[&Arg],
+) -> Result<()> {
+    for name in names {
+        if !opt_args.iter().any(|arg| arg.ident == name) {
+            return Err(Error::new(
+                name.span(),
+                format!("(optarg2chain) `{}` is not an optional argument", name),
+            ));
         }
-        arg_name.push(arg.ident);
-        arg_ty.push(arg.ty);
     }
+    Ok(())
+}
+
+// Returns the error type name if the builder has argument checks
+fn error_ident(
+    builder_struct_name: &syn::Ident,
+    error_kinds: &terminal::ErrorKinds,
+) -> Option<syn::Ident> {
+    if error_kinds.any() {
+        Some(format_ident!("{}Error", builder_struct_name))
+    } else {
+        None
+    }
+}
+
+// Field name to store the closure given by `_with` suffixed setter
+fn lazy_field_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_lazy_{}", ident)
+}
+
+// Lifetime of the closures given by `_with` suffixed setters, added to the builder struct
+fn lazy_lifetime() -> syn::Lifetime {
+    syn::Lifetime::new("'_optarg_lazy", Span::call_site())
+}
+
+// Name of `alloc` crate imported to box the closures given by `_with` suffixed setters
+fn alloc_crate_ident(builder_struct_name: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_alloc_{}", builder_struct_name)
+}
+
+// Generates the builder generics and the import of `alloc` crate if some arguments are lazy
+fn lazy_generics(
+    generics: &syn::Generics,
+    builder_struct_name: &syn::Ident,
+    opt_args: &[&Arg],
+) -> (syn::Generics, Option<proc_macro2::TokenStream>) {
+    let mut generics = generics.clone();
+    if !opt_args.iter().any(|arg| arg.opt.as_ref().unwrap().lazy) {
+        return (generics, None);
+    }
+    generics::add_lazy_lifetime(&mut generics);
+    let alloc_crate = alloc_crate_ident(builder_struct_name);
     (
-        arg_name,
-        arg_ty,
-        req_ident,
-        req_ty,
-        opt_ident,
-        opt_ty,
-        opt_default_value,
+        generics,
+        Some(quote! {
+            #[doc(hidden)]
+            extern crate alloc as #alloc_crate;
+        }),
     )
 }
 
+// Field name to store the error detected by `try_` prefixed setter
+fn error_field_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_error_{}", ident)
+}
+
+// Generates builder struct fields (name and type) for optional arguments.
+// All of them are `Option` and initialized by `None`.
+fn generate_opt_fields(
+    opt_args: &[&Arg],
+    error_ident: Option<&syn::Ident>,
+    alloc_crate: &syn::Ident,
+) -> (Vec<syn::Ident>, Vec<proc_macro2::TokenStream>) {
+    let lazy_lifetime = lazy_lifetime();
+    let mut idents = vec![];
+    let mut types = vec![];
+    for arg in opt_args {
+        let ident = arg.ident;
+        let ty = arg.ty;
+        idents.push(ident.clone());
+        types.push(quote! { core::option::Option<#ty> });
+        if arg.opt.as_ref().unwrap().lazy {
+            idents.push(lazy_field_ident(ident));
+            types.push(quote! {
+                core::option::Option<
+                    #alloc_crate::boxed::Box<dyn core::ops::FnOnce() -> #ty + #lazy_lifetime>,
+                >
+            });
+        }
+        if arg.opt.as_ref().unwrap().try_into {
+            idents.push(error_field_ident(ident));
+            let error_ident = error_ident.expect("error type for try_into");
+            types.push(quote! { core::option::Option<#error_ident> });
+        }
+    }
+    (idents, types)
+}
+
+// Generates `Clone` implementation for the builder struct.
+// Bounds for field types are given by HRTB to avoid errors by trivial bounds.
+fn generate_clone_impl(
+    builder_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    field_ident: &[&syn::Ident],
+    field_ty: &[proc_macro2::TokenStream],
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates: Vec<&syn::WherePredicate> = where_clause
+        .iter()
+        .flat_map(|w| w.predicates.iter())
+        .collect();
+    quote! {
+        impl #impl_generics core::clone::Clone for #builder_struct_name #ty_generics
+        where
+            #(#predicates,)*
+            #(for<'_optarg> #field_ty: core::clone::Clone,)*
+        {
+            fn clone(&self) -> Self {
+                #builder_struct_name {
+                    #(
+                        #field_ident: core::clone::Clone::clone(&self.#field_ident),
+                    )*
+                    _optarg_marker: core::marker::PhantomData,
+                }
+            }
+        }
+    }
+}
+
 fn erase_optarg_attr(sig: &mut syn::Signature) {
     for arg in sig.inputs.iter_mut() {
-        match arg {
-            syn::FnArg::Typed(pt) => {
-                pt.attrs.retain(|attr| {
-                    !attr.path.is_ident(ATTR_NAME_DEFAULT_ARG)
-                        && !attr.path.is_ident(ATTR_NAME_OPT_ARG)
-                });
-            }
-            _ => (),
+        if let syn::FnArg::Typed(pt) = arg {
+            pt.attrs.retain(|attr| {
+                !attr.path.is_ident(ATTR_NAME_DEFAULT_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_OPT_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_NONE_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_FLAG_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_COLLECT_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_FLATTEN_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_NESTED_ARG)
+                    && !attr.path.is_ident(ATTR_NAME_VARIANTS_ARG)
+            });
         }
     }
 }
 
-fn separate_attrs<'a>(
-    attrs: &'a [syn::Attribute],
-) -> (Vec<&'a syn::Attribute>, Vec<&'a syn::Attribute>) {
+fn separate_attrs(attrs: &[syn::Attribute]) -> (Vec<&syn::Attribute>, Vec<&syn::Attribute>) {
     let mut optarg_attrs = vec![];
     let mut other_attrs = vec![];
 
@@ -529,7 +2210,23 @@ fn separate_attrs<'a>(
     (optarg_attrs, other_attrs)
 }
 
+// Parses and removes `optarg_preset` attributes
+fn take_presets(attrs: &mut Vec<syn::Attribute>) -> Result<Vec<Preset>> {
+    let mut presets = vec![];
+    let mut others = vec![];
+    for attr in attrs.drain(..) {
+        if attr.path.is_ident(ATTR_NAME_PRESET) {
+            presets.push(attr.parse_args()?);
+        } else {
+            others.push(attr);
+        }
+    }
+    *attrs = others;
+    Ok(presets)
+}
+
 // Returns (receiver, reciever ident, receiver type, other args)
+#[allow(clippy::type_complexity)]
 fn separate_receiver<'a>(
     sig: &'a syn::Signature,
     self_ty: &syn::Type,
@@ -611,8 +2308,8 @@ fn check_sig(sig: &syn::Signature) -> Result<()> {
     }
 
     for arg in &sig.inputs {
-        match arg {
-            syn::FnArg::Typed(t) => match *t.pat {
+        if let syn::FnArg::Typed(t) = arg {
+            match *t.pat {
                 syn::Pat::Ident(syn::PatIdent { ref ident, .. }) => {
                     if ident == INNER_SELF_VAR {
                         return Err(Error::new(
@@ -627,8 +2324,7 @@ fn check_sig(sig: &syn::Signature) -> Result<()> {
                 _ => {
                     return Err(Error::new(t.span(), ERR_MSG_UNUSABLE_PAT));
                 }
-            },
-            _ => (),
+            }
         }
     }
     Ok(())
@@ -0,0 +1,163 @@
+//! Functions to generate overrides of default values scoped to closures
+
+use crate::{doc, Arg};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::fold::Fold;
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+const ERR_MSG_NON_STATIC_TYPE: &str =
+    "(optarg2chain) `overridable` needs optional arguments of `'static` types";
+
+// Name of the hidden associated function returning the thread local overrides of the argument
+fn override_fn_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_override_{}", ident)
+}
+
+// Type of the thread local overrides. Overrides are keyed by the concrete type of the argument
+// because a generic builder shares the thread local among its instantiations.
+fn override_map_ty() -> TokenStream {
+    quote! {
+        core::cell::RefCell<
+            std::collections::HashMap<core::any::TypeId, std::boxed::Box<dyn core::any::Any>>
+        >
+    }
+}
+
+// Type of the override stored in the thread local overrides
+fn override_ty(ty: &syn::Type) -> TokenStream {
+    quote! { std::boxed::Box<dyn core::ops::Fn() -> #ty> }
+}
+
+// Finds a lifetime other than `'static`
+struct LifetimeFinder(Option<syn::Lifetime>);
+
+impl Fold for LifetimeFinder {
+    fn fold_lifetime(&mut self, lifetime: syn::Lifetime) -> syn::Lifetime {
+        if lifetime.ident != "static" && self.0.is_none() {
+            self.0 = Some(lifetime.clone());
+        }
+        lifetime
+    }
+
+    fn fold_type_reference(&mut self, reference: syn::TypeReference) -> syn::TypeReference {
+        if reference.lifetime.is_none() && self.0.is_none() {
+            self.0 = Some(syn::Lifetime::new("'_", reference.and_token.span()));
+        }
+        syn::fold::fold_type_reference(self, reference)
+    }
+}
+
+/// Checks that types of optional arguments have no lifetime, and bounds them by `'static`
+/// since overrides are stored in the thread local storage
+pub fn static_generics(generics: &syn::Generics, opt_args: &[&Arg]) -> Result<syn::Generics> {
+    for arg in opt_args {
+        let mut finder = LifetimeFinder(None);
+        finder.fold_type(arg.ty.clone());
+        if let Some(lifetime) = finder.0 {
+            return Err(Error::new(lifetime.span(), ERR_MSG_NON_STATIC_TYPE));
+        }
+    }
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    for arg in opt_args {
+        let ty = arg.ty;
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #ty: 'static });
+    }
+    Ok(generics)
+}
+
+/// Generates `with_default_` prefixed functions and the thread local storage for overrides
+pub fn generate_overrides(vis: &syn::Visibility, opt_args: &[&Arg]) -> Vec<TokenStream> {
+    let override_map_ty = override_map_ty();
+    opt_args
+        .iter()
+        .map(|arg| {
+            let ident = arg.ident;
+            let ty = arg.ty;
+            let override_fn = override_fn_ident(ident);
+            let override_ty = override_ty(ty);
+            let with_default_ident = format_ident!("with_default_{}", ident);
+            let doc_with_default = doc::with_default(ident);
+            quote! {
+                #[doc(hidden)]
+                fn #override_fn() -> &'static std::thread::LocalKey<#override_map_ty> {
+                    std::thread_local! {
+                        static OVERRIDE: #override_map_ty =
+                            core::cell::RefCell::new(std::collections::HashMap::new());
+                    }
+                    &OVERRIDE
+                }
+
+                #doc_with_default
+                #vis fn #with_default_ident<_OPTARG_VALUE, _OPTARG_FN, _OPTARG_R>(
+                    value: _OPTARG_VALUE,
+                    f: _OPTARG_FN,
+                ) -> _OPTARG_R
+                where
+                    _OPTARG_VALUE: core::convert::Into<#ty>,
+                    _OPTARG_FN: core::ops::FnOnce() -> _OPTARG_R,
+                    #ty: core::clone::Clone + 'static,
+                {
+                    // Restores the previous override even if `f` panics
+                    struct Restore(
+                        &'static std::thread::LocalKey<#override_map_ty>,
+                        core::any::TypeId,
+                        core::option::Option<std::boxed::Box<dyn core::any::Any>>,
+                    );
+                    impl core::ops::Drop for Restore {
+                        fn drop(&mut self) {
+                            let previous = self.2.take();
+                            self.0.with(|cell| {
+                                let mut overrides = cell.borrow_mut();
+                                match previous {
+                                    core::option::Option::Some(previous) => {
+                                        overrides.insert(self.1, previous);
+                                    }
+                                    core::option::Option::None => {
+                                        overrides.remove(&self.1);
+                                    }
+                                }
+                            });
+                        }
+                    }
+
+                    let value: #ty = <_OPTARG_VALUE as core::convert::Into<#ty>>::into(value);
+                    let value: #override_ty = std::boxed::Box::new(move || {
+                        core::clone::Clone::clone(&value)
+                    });
+                    let key = Self::#override_fn();
+                    let type_id = core::any::TypeId::of::<#ty>();
+                    let previous = key.with(|cell| {
+                        cell.borrow_mut().insert(type_id, std::boxed::Box::new(value))
+                    });
+                    let _restore = Restore(key, type_id, previous);
+                    f()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates expression to get the overridden default value, or evaluate `default_value` if not overridden
+pub fn generate_overridden_default(arg: &Arg, default_value: TokenStream) -> TokenStream {
+    let override_fn = override_fn_ident(arg.ident);
+    let ty = arg.ty;
+    let override_ty = override_ty(ty);
+    quote! {
+        match Self::#override_fn().with(|cell| {
+            cell.borrow()
+                .get(&core::any::TypeId::of::<#ty>())
+                .and_then(|value| value.downcast_ref::<#override_ty>())
+                .map(|f| f())
+        }) {
+            core::option::Option::Some(value) => value,
+            core::option::Option::None => {
+                #default_value
+            }
+        }
+    }
+}
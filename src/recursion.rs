@@ -0,0 +1,161 @@
+//! Functions to redirect recursive calls with all arguments to the original function
+
+use syn::fold::{self, Fold};
+
+/// Rewrites calls of the function with all arguments in the body to calls of `target`
+pub struct RedirectFullCalls<'a> {
+    /// Name of the original function or method
+    pub ident: &'a syn::Ident,
+    /// Called by `Self::ident(..)` instead of `ident(..)`
+    pub method: bool,
+    /// Number of all arguments including the receiver
+    pub arity: usize,
+    /// Number of arguments of `self.ident(..)` calls if the method has a receiver
+    pub method_call_arity: Option<usize>,
+    /// Name of the function taking all arguments
+    pub target: &'a syn::Ident,
+    /// The name of the function is shadowed by a local binding or item in the current scope
+    pub shadowed: bool,
+}
+
+impl RedirectFullCalls<'_> {
+    // Returns true if the path refers to the original function
+    fn is_target_path(&self, path: &syn::Path) -> bool {
+        let segments: Vec<&syn::PathSegment> = path.segments.iter().collect();
+        match segments.as_slice() {
+            [name] if !self.method => path.leading_colon.is_none() && name.ident == *self.ident,
+            [self_ty, name] if self.method => {
+                self_ty.ident == "Self" && self_ty.arguments.is_empty() && name.ident == *self.ident
+            }
+            _ => false,
+        }
+    }
+
+    // Returns true if the pattern binds the name of the function
+    fn binds(&self, pat: &syn::Pat) -> bool {
+        pat_bindings(pat).contains(self.ident)
+    }
+
+    // Returns true if the condition of `if let` or `while let` binds the name of the function
+    fn let_binds(&self, cond: &syn::Expr) -> bool {
+        matches!(cond, syn::Expr::Let(expr) if self.binds(&expr.pat))
+    }
+
+    // Folds `f` with the name shadowed if `shadow` is true, and restores the state after that
+    fn scoped<T>(&mut self, shadow: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let shadowed = self.shadowed;
+        self.shadowed |= shadow;
+        let result = f(self);
+        self.shadowed = shadowed;
+        result
+    }
+}
+
+/// Returns the names bound by a pattern
+pub fn pat_bindings(pat: &syn::Pat) -> Vec<syn::Ident> {
+    let mut finder = BindingFinder(vec![]);
+    finder.fold_pat(pat.clone());
+    finder.0
+}
+
+// Collects the names bound by a pattern
+struct BindingFinder(Vec<syn::Ident>);
+
+impl Fold for BindingFinder {
+    fn fold_pat_ident(&mut self, pat: syn::PatIdent) -> syn::PatIdent {
+        self.0.push(pat.ident.clone());
+        fold::fold_pat_ident(self, pat)
+    }
+}
+
+impl Fold for RedirectFullCalls<'_> {
+    fn fold_expr_call(&mut self, call: syn::ExprCall) -> syn::ExprCall {
+        let mut call = fold::fold_expr_call(self, call);
+        if call.args.len() == self.arity && !self.shadowed {
+            if let syn::Expr::Path(syn::ExprPath {
+                qself: None, path, ..
+            }) = &mut *call.func
+            {
+                if self.is_target_path(path) {
+                    path.segments.last_mut().unwrap().ident = self.target.clone();
+                }
+            }
+        }
+        call
+    }
+
+    fn fold_expr_method_call(&mut self, call: syn::ExprMethodCall) -> syn::ExprMethodCall {
+        let mut call = fold::fold_expr_method_call(self, call);
+        let self_receiver = matches!(
+            &*call.receiver,
+            syn::Expr::Path(syn::ExprPath { qself: None, path, .. }) if path.is_ident("self")
+        );
+        if self_receiver
+            && Some(call.args.len()) == self.method_call_arity
+            && call.method == *self.ident
+        {
+            call.method = self.target.clone();
+        }
+        call
+    }
+
+    // Local bindings shadow the function called by `ident(..)`, but not `Self::ident(..)`
+    fn fold_block(&mut self, block: syn::Block) -> syn::Block {
+        let shadow = !self.method
+            && block.stmts.iter().any(|stmt| {
+                matches!(stmt, syn::Stmt::Item(syn::Item::Fn(f)) if f.sig.ident == *self.ident)
+            });
+        self.scoped(shadow, |this| fold::fold_block(this, block))
+    }
+
+    // The binding is visible in the rest of the enclosing block, which `fold_block` restores
+    fn fold_local(&mut self, local: syn::Local) -> syn::Local {
+        let local = fold::fold_local(self, local);
+        if !self.method && self.binds(&local.pat) {
+            self.shadowed = true;
+        }
+        local
+    }
+
+    fn fold_expr_closure(&mut self, closure: syn::ExprClosure) -> syn::ExprClosure {
+        let shadow = !self.method && closure.inputs.iter().any(|pat| self.binds(pat));
+        self.scoped(shadow, |this| fold::fold_expr_closure(this, closure))
+    }
+
+    fn fold_arm(&mut self, arm: syn::Arm) -> syn::Arm {
+        let shadow = !self.method && self.binds(&arm.pat);
+        self.scoped(shadow, |this| fold::fold_arm(this, arm))
+    }
+
+    fn fold_expr_for_loop(&mut self, mut expr: syn::ExprForLoop) -> syn::ExprForLoop {
+        expr.expr = Box::new(self.fold_expr(*expr.expr));
+        let shadow = !self.method && self.binds(&expr.pat);
+        let body = expr.body;
+        expr.body = self.scoped(shadow, |this| this.fold_block(body));
+        expr
+    }
+
+    fn fold_expr_if(&mut self, mut expr: syn::ExprIf) -> syn::ExprIf {
+        expr.cond = Box::new(self.fold_expr(*expr.cond));
+        let shadow = !self.method && self.let_binds(&expr.cond);
+        let then_branch = expr.then_branch;
+        expr.then_branch = self.scoped(shadow, |this| this.fold_block(then_branch));
+        expr.else_branch = expr
+            .else_branch
+            .map(|(else_token, else_expr)| (else_token, Box::new(self.fold_expr(*else_expr))));
+        expr
+    }
+
+    fn fold_expr_while(&mut self, mut expr: syn::ExprWhile) -> syn::ExprWhile {
+        expr.cond = Box::new(self.fold_expr(*expr.cond));
+        let shadow = !self.method && self.let_binds(&expr.cond);
+        let body = expr.body;
+        expr.body = self.scoped(shadow, |this| this.fold_block(body));
+        expr
+    }
+
+    // Items in the body have their own scopes
+    fn fold_item(&mut self, item: syn::Item) -> syn::Item {
+        item
+    }
+}
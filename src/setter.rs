@@ -0,0 +1,591 @@
+//! Functions to generate setter methods of builder structs
+
+use crate::{
+    doc, error_field_ident, lazy_field_ident, lazy_lifetime, Arg, NestedArgAttr, OptArgKind,
+    Preset, SetterFlavor,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Generates setter methods for optional arguments
+pub fn generate_setters(
+    vis: &syn::Visibility,
+    opt_args: &[&Arg],
+    error_ident: Option<&syn::Ident>,
+    alloc_crate: &syn::Ident,
+) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .map(|arg| generate_setter(vis, arg, error_ident, alloc_crate))
+        .collect()
+}
+
+fn generate_setter(
+    vis: &syn::Visibility,
+    arg: &Arg,
+    error_ident: Option<&syn::Ident>,
+    alloc_crate: &syn::Ident,
+) -> TokenStream {
+    let ident = arg.ident;
+    let ty = arg.ty;
+    let opt = arg.opt.as_ref().expect("setter for required argument");
+    let doc_setter = doc::setter(ident);
+    let maybe_ident = format_ident!("maybe_{}", ident);
+    let if_ident = format_ident!("{}_if", ident);
+    let doc_if_setter = doc::if_setter(ident);
+
+    match &opt.kind {
+        OptArgKind::Value | OptArgKind::Collect | OptArgKind::Flatten(_) => {
+            let doc_maybe_setter = doc::maybe_some_setter(ident);
+            let collect_setters = if let OptArgKind::Collect = opt.kind {
+                Some(generate_collect_setters(vis, arg))
+            } else {
+                None
+            };
+            let try_setter = if opt.try_into {
+                Some(generate_try_setter(vis, arg, error_ident.unwrap()))
+            } else {
+                None
+            };
+            let lazy_setter = if opt.lazy {
+                Some(generate_lazy_setter(vis, arg, alloc_crate))
+            } else {
+                None
+            };
+            let clear_overwritten = generate_clear_overwritten(arg);
+            let SetterParam {
+                generics,
+                param_ty,
+                where_clause,
+                convert,
+            } = SetterParam::new(opt.setter, ty);
+            quote! {
+                #doc_setter
+                #vis fn #ident #generics(mut self, value: #param_ty) -> Self #where_clause {
+                    let value = #convert;
+                    self.#ident = Some(value);
+                    #clear_overwritten
+                    self
+                }
+
+                #doc_maybe_setter
+                #vis fn #maybe_ident(mut self, value: core::option::Option<#ty>) -> Self {
+                    if let core::option::Option::Some(value) = value {
+                        self.#ident = Some(value);
+                        #clear_overwritten
+                    }
+                    self
+                }
+
+                #doc_if_setter
+                #vis fn #if_ident #generics(self, cond: bool, value: #param_ty) -> Self #where_clause {
+                    if cond {
+                        self.#ident(value)
+                    } else {
+                        self
+                    }
+                }
+
+                #try_setter
+
+                #lazy_setter
+
+                #collect_setters
+            }
+        }
+        OptArgKind::None(inner_ty) => {
+            let doc_maybe_setter = doc::maybe_setter(ident);
+            let SetterParam {
+                generics,
+                param_ty,
+                where_clause,
+                convert,
+            } = SetterParam::new(opt.setter, inner_ty);
+            quote! {
+                #doc_setter
+                #vis fn #ident #generics(mut self, value: #param_ty) -> Self #where_clause {
+                    let value = #convert;
+                    self.#ident = Some(core::option::Option::Some(value));
+                    self
+                }
+
+                #doc_maybe_setter
+                #vis fn #maybe_ident(mut self, value: core::option::Option<#inner_ty>) -> Self {
+                    self.#ident = Some(value);
+                    self
+                }
+
+                #doc_if_setter
+                #vis fn #if_ident #generics(self, cond: bool, value: #param_ty) -> Self #where_clause {
+                    if cond {
+                        self.#ident(value)
+                    } else {
+                        self
+                    }
+                }
+            }
+        }
+        OptArgKind::Nested(nested) => {
+            let doc_setter = doc::nested_setter(ident);
+            let doc_maybe_setter = doc::maybe_some_setter(ident);
+            let NestedSetterParam {
+                generics,
+                where_clause,
+                build,
+            } = NestedSetterParam::new(nested);
+            quote! {
+                #doc_setter
+                #vis fn #ident #generics(mut self, f: _OPTARG_FN) -> Self #where_clause {
+                    self.#ident = Some(#build);
+                    self
+                }
+
+                #doc_maybe_setter
+                #vis fn #maybe_ident(mut self, value: core::option::Option<#ty>) -> Self {
+                    if let core::option::Option::Some(value) = value {
+                        self.#ident = Some(value);
+                    }
+                    self
+                }
+
+                #doc_if_setter
+                #vis fn #if_ident #generics(self, cond: bool, f: _OPTARG_FN) -> Self #where_clause {
+                    if cond {
+                        self.#ident(f)
+                    } else {
+                        self
+                    }
+                }
+            }
+        }
+        OptArgKind::Flag(default) => {
+            let no_ident = format_ident!("no_{}", ident);
+            let doc_setter = doc::flag_setter(ident);
+            let doc_maybe_setter = doc::flag_maybe_setter(ident);
+            let doc_if_setter = doc::flag_if_setter(ident);
+            let unsetter = if *default {
+                let doc_unsetter = doc::flag_unsetter(ident);
+                Some(quote! {
+                    #doc_unsetter
+                    #vis fn #no_ident(mut self) -> Self {
+                        self.#ident = Some(false);
+                        self
+                    }
+                })
+            } else {
+                None
+            };
+            quote! {
+                #doc_setter
+                #vis fn #ident(mut self) -> Self {
+                    self.#ident = Some(true);
+                    self
+                }
+
+                #unsetter
+
+                #doc_maybe_setter
+                #vis fn #maybe_ident(mut self, value: core::option::Option<bool>) -> Self {
+                    if let core::option::Option::Some(value) = value {
+                        self.#ident = Some(value);
+                    }
+                    self
+                }
+
+                #doc_if_setter
+                #vis fn #if_ident(self, cond: bool) -> Self {
+                    if cond {
+                        self.#ident()
+                    } else {
+                        self
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Generates setters for required arguments. `set_` prefixed setters are also generated if `in_place` is true.
+pub fn generate_required_setters(
+    vis: &syn::Visibility,
+    req_ident: &[&syn::Ident],
+    req_ty: &[&syn::Type],
+    flavor: SetterFlavor,
+    in_place: bool,
+) -> Vec<TokenStream> {
+    req_ident
+        .iter()
+        .zip(req_ty)
+        .map(|(ident, ty)| {
+            let SetterParam {
+                generics,
+                param_ty,
+                where_clause,
+                convert,
+            } = SetterParam::new(flavor, ty);
+            let doc_setter = doc::required_setter(ident);
+            let in_place_setter = if in_place {
+                let set_ident = format_ident!("set_{}", ident);
+                Some(quote! {
+                    #doc_setter
+                    #vis fn #set_ident #generics(&mut self, value: #param_ty) -> &mut Self #where_clause {
+                        self.#ident = #convert;
+                        self
+                    }
+                })
+            } else {
+                None
+            };
+            quote! {
+                #doc_setter
+                #vis fn #ident #generics(mut self, value: #param_ty) -> Self #where_clause {
+                    self.#ident = #convert;
+                    self
+                }
+
+                #in_place_setter
+            }
+        })
+        .collect()
+}
+
+/// Generates `reset_` prefixed methods to restore default values.
+/// `opt_field_ident` is all `Option` fields of the builder struct.
+pub fn generate_reset_methods(
+    vis: &syn::Visibility,
+    opt_args: &[&Arg],
+    opt_field_ident: &[syn::Ident],
+) -> TokenStream {
+    let reset_methods = opt_args.iter().map(|arg| {
+        let ident = arg.ident;
+        let reset_ident = format_ident!("reset_{}", ident);
+        let doc_reset = doc::reset(ident);
+        let clear_overwritten = generate_clear_overwritten(arg);
+        quote! {
+            #doc_reset
+            #vis fn #reset_ident(mut self) -> Self {
+                self.#ident = core::option::Option::None;
+                #clear_overwritten
+                self
+            }
+        }
+    });
+    let doc_reset_all = doc::reset_all();
+
+    quote! {
+        #(#reset_methods)*
+
+        #doc_reset_all
+        #vis fn reset_all(mut self) -> Self {
+            #(
+                self.#opt_field_ident = core::option::Option::None;
+            )*
+            self
+        }
+    }
+}
+
+/// Generates `set_` prefixed setter methods taking `&mut self`
+pub fn generate_in_place_setters(vis: &syn::Visibility, opt_args: &[&Arg]) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .map(|arg| {
+            let ident = arg.ident;
+            let opt = arg.opt.as_ref().unwrap();
+            let set_ident = format_ident!("set_{}", ident);
+            let doc_setter = doc::in_place_setter(ident);
+            let clear_overwritten = generate_clear_overwritten(arg);
+            let (target_ty, wrap): (&syn::Type, fn(TokenStream) -> TokenStream) = match &opt.kind
+            {
+                OptArgKind::Value | OptArgKind::Collect | OptArgKind::Flatten(_) => {
+                    (arg.ty, |value| value)
+                }
+                OptArgKind::None(inner_ty) => (inner_ty, |value| {
+                    quote! { core::option::Option::Some(#value) }
+                }),
+                OptArgKind::Flag(_) => {
+                    return quote! {
+                        #doc_setter
+                        #vis fn #set_ident(&mut self, value: bool) -> &mut Self {
+                            self.#ident = Some(value);
+                            self
+                        }
+                    };
+                }
+                OptArgKind::Nested(nested) => {
+                    let NestedSetterParam {
+                        generics,
+                        where_clause,
+                        build,
+                    } = NestedSetterParam::new(nested);
+                    return quote! {
+                        #doc_setter
+                        #vis fn #set_ident #generics(&mut self, f: _OPTARG_FN) -> &mut Self #where_clause {
+                            self.#ident = Some(#build);
+                            self
+                        }
+                    };
+                }
+            };
+            let SetterParam {
+                generics,
+                param_ty,
+                where_clause,
+                convert,
+            } = SetterParam::new(opt.setter, target_ty);
+            let value = wrap(quote! { value });
+            quote! {
+                #doc_setter
+                #vis fn #set_ident #generics(&mut self, value: #param_ty) -> &mut Self #where_clause {
+                    let value = #convert;
+                    self.#ident = Some(#value);
+                    #clear_overwritten
+                    self
+                }
+            }
+        })
+        .collect()
+}
+
+/// Parameter of setter and the expression to convert `value` into the argument type
+pub struct SetterParam {
+    pub generics: Option<TokenStream>,
+    pub param_ty: TokenStream,
+    pub where_clause: Option<TokenStream>,
+    pub convert: TokenStream,
+}
+
+impl SetterParam {
+    pub fn new(flavor: SetterFlavor, ty: &syn::Type) -> SetterParam {
+        match flavor {
+            SetterFlavor::Into => SetterParam {
+                generics: Some(quote! { <_OPTARG_VALUE: core::convert::Into<#ty>> }),
+                param_ty: quote! { _OPTARG_VALUE },
+                where_clause: None,
+                convert: quote! { <_OPTARG_VALUE as core::convert::Into<#ty>>::into(value) },
+            },
+            SetterFlavor::Exact => SetterParam {
+                generics: None,
+                param_ty: quote! { #ty },
+                where_clause: None,
+                convert: quote! { value },
+            },
+            SetterFlavor::CloneFromRef => SetterParam {
+                generics: None,
+                param_ty: quote! { &#ty },
+                where_clause: Some(quote! { where #ty: core::clone::Clone }),
+                convert: quote! { <#ty as core::clone::Clone>::clone(value) },
+            },
+        }
+    }
+}
+
+// Parameter of setter taking a closure to configure the nested builder, and the expression to build the value
+struct NestedSetterParam {
+    generics: TokenStream,
+    where_clause: TokenStream,
+    build: TokenStream,
+}
+
+impl NestedSetterParam {
+    fn new(nested: &NestedArgAttr) -> NestedSetterParam {
+        let NestedArgAttr {
+            ctor,
+            builder_ty,
+            terminal_method_name,
+        } = nested;
+        NestedSetterParam {
+            generics: quote! { <_OPTARG_FN> },
+            where_clause: quote! {
+                where _OPTARG_FN: core::ops::FnOnce(#builder_ty) -> #builder_ty
+            },
+            build: quote! { f(#ctor).#terminal_method_name() },
+        }
+    }
+}
+
+// Generates setter converting the value by `TryInto`. The conversion error is kept in the builder
+// until the argument is set again.
+fn generate_try_setter(vis: &syn::Visibility, arg: &Arg, error_ident: &syn::Ident) -> TokenStream {
+    let ident = arg.ident;
+    let ty = arg.ty;
+    let try_ident = format_ident!("try_{}", ident);
+    let arg_name = ident.to_string();
+    let error_field = error_field_ident(ident);
+    let doc_try_setter = doc::try_setter(ident);
+    let clear_overwritten = generate_clear_overwritten(arg);
+    quote! {
+        #doc_try_setter
+        #vis fn #try_ident<_OPTARG_VALUE>(mut self, value: _OPTARG_VALUE) -> Self
+        where
+            _OPTARG_VALUE: core::convert::TryInto<#ty>,
+        {
+            match <_OPTARG_VALUE as core::convert::TryInto<#ty>>::try_into(value) {
+                core::result::Result::Ok(value) => {
+                    self.#ident = Some(value);
+                    #clear_overwritten
+                }
+                core::result::Result::Err(_) => {
+                    self.#error_field = Some(#error_ident::Conversion { arg: #arg_name });
+                }
+            }
+            self
+        }
+    }
+}
+
+// Generates statements clearing the closure given by `_with` suffixed setter and the error kept by
+// `try_` prefixed setter, which are overwritten by a new value of the argument
+fn generate_clear_overwritten(arg: &Arg) -> TokenStream {
+    let opt = arg.opt.as_ref().unwrap();
+    let clear_lazy = if opt.lazy {
+        let lazy_field = lazy_field_ident(arg.ident);
+        Some(quote! { self.#lazy_field = core::option::Option::None; })
+    } else {
+        None
+    };
+    let clear_error = if opt.try_into {
+        let error_field = error_field_ident(arg.ident);
+        Some(quote! { self.#error_field = core::option::Option::None; })
+    } else {
+        None
+    };
+    quote! {
+        #clear_lazy
+        #clear_error
+    }
+}
+
+// Generates setter taking a closure that is called by the terminal method
+fn generate_lazy_setter(vis: &syn::Visibility, arg: &Arg, alloc_crate: &syn::Ident) -> TokenStream {
+    let ident = arg.ident;
+    let lazy_lifetime = lazy_lifetime();
+    let ty = arg.ty;
+    let with_ident = format_ident!("{}_with", ident);
+    let lazy_field = lazy_field_ident(ident);
+    let doc_lazy_setter = doc::lazy_setter(ident);
+    let clear_error = if arg.opt.as_ref().unwrap().try_into {
+        let error_field = error_field_ident(ident);
+        Some(quote! { self.#error_field = core::option::Option::None; })
+    } else {
+        None
+    };
+
+    quote! {
+        #doc_lazy_setter
+        #vis fn #with_ident<_OPTARG_FN>(mut self, f: _OPTARG_FN) -> Self
+        where
+            _OPTARG_FN: core::ops::FnOnce() -> #ty + #lazy_lifetime,
+        {
+            self.#ident = core::option::Option::None;
+            self.#lazy_field = Some(#alloc_crate::boxed::Box::new(f));
+            #clear_error
+            self
+        }
+    }
+}
+
+// Generates setters that accumulate elements into the collection argument
+fn generate_collect_setters(vis: &syn::Visibility, arg: &Arg) -> TokenStream {
+    let ident = arg.ident;
+    let ty = arg.ty;
+    let push_ident = format_ident!("push_{}", ident);
+    let insert_ident = format_ident!("insert_{}", ident);
+    let extend_ident = format_ident!("extend_{}", ident);
+    let doc_push_setter = doc::push_setter(ident);
+    let doc_insert_setter = doc::insert_setter(ident);
+    let doc_extend_setter = doc::extend_setter(ident);
+
+    quote! {
+        #doc_push_setter
+        #vis fn #push_ident<_OPTARG_ITEM>(mut self, item: _OPTARG_ITEM) -> Self
+        where
+            #ty: core::iter::Extend<_OPTARG_ITEM>,
+        {
+            self.#ident
+                .get_or_insert_with(<#ty as core::default::Default>::default)
+                .extend(core::iter::once(item));
+            self
+        }
+
+        #doc_insert_setter
+        #vis fn #insert_ident<_OPTARG_KEY, _OPTARG_VALUE>(
+            mut self, key: _OPTARG_KEY, value: _OPTARG_VALUE) -> Self
+        where
+            #ty: core::iter::Extend<(_OPTARG_KEY, _OPTARG_VALUE)>,
+        {
+            self.#ident
+                .get_or_insert_with(<#ty as core::default::Default>::default)
+                .extend(core::iter::once((key, value)));
+            self
+        }
+
+        #doc_extend_setter
+        #vis fn #extend_ident<_OPTARG_ITER: core::iter::IntoIterator>(
+            mut self, iter: _OPTARG_ITER) -> Self
+        where
+            #ty: core::iter::Extend<<_OPTARG_ITER as core::iter::IntoIterator>::Item>,
+        {
+            self.#ident
+                .get_or_insert_with(<#ty as core::default::Default>::default)
+                .extend(iter);
+            self
+        }
+    }
+}
+
+/// Generates methods setting the values of presets by `maybe_` prefixed setters.
+/// Values are given the types of the arguments, so that literals are typed as setters expect.
+pub fn generate_preset_methods(
+    vis: &syn::Visibility,
+    presets: &[Preset],
+    opt_args: &[&Arg],
+) -> Vec<TokenStream> {
+    presets
+        .iter()
+        .map(|preset| {
+            let preset_ident = &preset.ident;
+            let arg_ident: Vec<&syn::Ident> =
+                preset.values.iter().map(|(ident, _)| ident).collect();
+            let maybe_ident = arg_ident
+                .iter()
+                .map(|ident| format_ident!("maybe_{}", ident));
+            let value_ident: Vec<syn::Ident> = arg_ident
+                .iter()
+                .map(|ident| format_ident!("_optarg_preset_{}", ident))
+                .collect();
+            let value_ty = arg_ident.iter().map(|ident| {
+                let arg = opt_args
+                    .iter()
+                    .find(|arg| arg.ident == *ident)
+                    .expect("preset for unknown argument");
+                maybe_setter_ty(arg)
+            });
+            let value = preset.values.iter().map(|(_, value)| value);
+            let doc_preset = doc::preset(&arg_ident);
+            quote! {
+                #doc_preset
+                #vis fn #preset_ident(self) -> Self {
+                    #(
+                        let #value_ident: #value_ty = #value;
+                    )*
+                    self #(
+                        .#maybe_ident(core::option::Option::Some(#value_ident))
+                    )*
+                }
+            }
+        })
+        .collect()
+}
+
+// Returns the type of the value given to `maybe_` prefixed setter
+fn maybe_setter_ty(arg: &Arg) -> TokenStream {
+    match &arg.opt.as_ref().unwrap().kind {
+        OptArgKind::None(inner_ty) => quote! { #inner_ty },
+        OptArgKind::Flag(_) => quote! { bool },
+        _ => {
+            let ty = arg.ty;
+            quote! { #ty }
+        }
+    }
+}
@@ -0,0 +1,760 @@
+//! Functions to generate terminal methods of builder structs
+
+use crate::{
+    dependency, doc, error_field_ident, lazy_field_ident, overrides, Arg, ArgGroups, EnvDefault,
+    EnvErrorPolicy, OptArgKind,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+
+pub struct Terminal<'a> {
+    pub vis: &'a syn::Visibility,
+    pub asyncness: &'a Option<syn::token::Async>,
+    pub ident: &'a syn::Ident,
+    pub output: &'a syn::ReturnType,
+    pub where_clause: Option<&'a syn::WhereClause>,
+    pub doc: &'a syn::Attribute,
+    /// Error type name if the builder has checks
+    pub error_ident: Option<&'a syn::Ident>,
+    /// Function to validate all arguments
+    pub validate: Option<&'a syn::Path>,
+    pub groups: &'a ArgGroups,
+    /// Default values can be overridden by `with_default_` prefixed functions
+    pub overridable: bool,
+    /// Async terminal methods return boxed futures
+    pub boxed_future: Option<&'a BoxedFuture>,
+    /// Names of all arguments passed to the original function
+    pub arg_name: &'a [&'a syn::Ident],
+    /// Items and statements at the beginning of the method
+    pub prologue: TokenStream,
+    /// Expression to call the original function
+    pub call: TokenStream,
+}
+
+/// Boxed future returned by async terminal methods given by `boxed_future` option
+pub struct BoxedFuture {
+    /// The future is `Send`
+    pub send: bool,
+    /// Lifetime of the future
+    pub lifetime: TokenStream,
+}
+
+impl BoxedFuture {
+    pub fn new(send: bool, generics: &syn::Generics) -> BoxedFuture {
+        BoxedFuture {
+            send,
+            lifetime: future_lifetime(generics),
+        }
+    }
+
+    // Type of the boxed future resolving to `output_ty`
+    fn ty(&self, output_ty: &TokenStream) -> TokenStream {
+        let lifetime = &self.lifetime;
+        let send = if self.send {
+            Some(quote! { + core::marker::Send })
+        } else {
+            None
+        };
+        quote! {
+            core::pin::Pin<
+                std::boxed::Box<dyn core::future::Future<Output = #output_ty> #send + #lifetime>
+            >
+        }
+    }
+}
+
+// Returns the first lifetime parameter, or `'static` if there is none. A trait object has a single
+// lifetime bound, so `Self` outliving it requires the other lifetime parameters to outlive it.
+fn future_lifetime(generics: &syn::Generics) -> TokenStream {
+    match generics.lifetimes().next() {
+        Some(def) => {
+            let lifetime = &def.lifetime;
+            quote! { #lifetime }
+        }
+        None => quote! { 'static },
+    }
+}
+
+/// Generates terminal methods. Optional arguments are bound in the order of `opt_args`. If the builder has checks, `try_` prefixed terminal method is also generated.
+pub fn generate_terminal(terminal: &Terminal, opt_args: &[&Arg]) -> TokenStream {
+    let Terminal {
+        vis,
+        asyncness,
+        ident,
+        output,
+        where_clause,
+        doc,
+        error_ident,
+        validate,
+        groups,
+        overridable,
+        boxed_future,
+        arg_name,
+        prologue,
+        call,
+    } = terminal;
+    let await_ = if asyncness.is_some() {
+        Some(quote! { .await })
+    } else {
+        None
+    };
+    let opt_bindings = generate_opt_bindings(opt_args, *error_ident, *overridable);
+    // Terminal method with the body, which returns a boxed future if `boxed_future` is given
+    let generate_fn = |ident: &syn::Ident, output: &syn::ReturnType, body: TokenStream| {
+        if let Some(boxed_future) = boxed_future {
+            let future_ty = boxed_future.ty(&return_ty(output));
+            let lifetime = &boxed_future.lifetime;
+            let predicates = where_clause.iter().flat_map(|w| w.predicates.iter());
+            quote! {
+                #vis fn #ident(self) -> #future_ty
+                where
+                    #(#predicates,)*
+                    Self: #lifetime,
+                {
+                    std::boxed::Box::pin(async move { #body })
+                }
+            }
+        } else {
+            quote! {
+                #vis #asyncness fn #ident(self) #output #where_clause {
+                    #body
+                }
+            }
+        }
+    };
+
+    let error_ident = if let Some(error_ident) = error_ident {
+        error_ident
+    } else {
+        let terminal_fn = generate_fn(
+            ident,
+            output,
+            quote! {
+                #prologue
+                #(#opt_bindings)*
+                #call
+                #await_
+            },
+        );
+        return quote! {
+            #doc
+            #terminal_fn
+        };
+    };
+
+    let try_ident = format_ident!("try_{}", ident);
+    let error_fields = opt_args
+        .iter()
+        .filter(|arg| arg.opt.as_ref().unwrap().try_into)
+        .map(|arg| error_field_ident(arg.ident));
+    let doc_try_terminal_method = doc::try_terminal_method(ident);
+    let group_checks = generate_group_checks(opt_args, groups, error_ident);
+    let checks = generate_checks(opt_args, *validate, arg_name, error_ident);
+    let output_ty = return_ty(output);
+    let try_output: syn::ReturnType =
+        syn::parse_quote! { -> core::result::Result<#output_ty, #error_ident> };
+    let terminal_fn = generate_fn(
+        ident,
+        output,
+        quote! {
+            match self.#try_ident()#await_ {
+                core::result::Result::Ok(value) => value,
+                core::result::Result::Err(e) => panic!("{}", e),
+            }
+        },
+    );
+    let try_terminal_fn = generate_fn(
+        &try_ident,
+        &try_output,
+        quote! {
+            #(
+                if let core::option::Option::Some(e) = self.#error_fields {
+                    return core::result::Result::Err(e);
+                }
+            )*
+            #(#group_checks)*
+            #prologue
+            #(#opt_bindings)*
+            #(#checks)*
+            core::result::Result::Ok(#call #await_)
+        },
+    );
+
+    quote! {
+        #doc
+        #[doc = ""]
+        #[doc = "# Panics"]
+        #[doc = "Panics if an argument check failed."]
+        #terminal_fn
+
+        #doc_try_terminal_method
+        #try_terminal_fn
+    }
+}
+
+// Returns the type returned by the function
+fn return_ty(output: &syn::ReturnType) -> TokenStream {
+    match output {
+        syn::ReturnType::Default => quote! { () },
+        syn::ReturnType::Type(_, ty) => quote! { #ty },
+    }
+}
+
+/// Generates `_cloned` suffixed terminal method taking `&self`
+pub fn generate_cloned_terminal(
+    vis: &syn::Visibility,
+    asyncness: &Option<syn::token::Async>,
+    ident: &syn::Ident,
+    output: &syn::ReturnType,
+    where_clause: Option<&syn::WhereClause>,
+) -> TokenStream {
+    let cloned_ident = format_ident!("{}_cloned", ident);
+    let await_ = asyncness.map(|_| quote! { .await });
+    let predicates: Vec<&syn::WherePredicate> = where_clause
+        .iter()
+        .flat_map(|w| w.predicates.iter())
+        .collect();
+    let doc_cloned_terminal_method = doc::cloned_terminal_method(ident);
+    quote! {
+        #doc_cloned_terminal_method
+        #vis #asyncness fn #cloned_ident(&self) #output
+        where
+            #(#predicates,)*
+            for<'_optarg> Self: core::clone::Clone,
+        {
+            core::clone::Clone::clone(self).#ident()#await_
+        }
+    }
+}
+
+/// Generates `_blocking` suffixed terminal methods driving the futures of async terminal methods
+/// on the current thread. `try_` prefixed one is also generated if the builder has checks.
+pub fn generate_blocking_terminals(
+    vis: &syn::Visibility,
+    ident: &syn::Ident,
+    output: &syn::ReturnType,
+    where_clause: Option<&syn::WhereClause>,
+    error_ident: Option<&syn::Ident>,
+) -> TokenStream {
+    let blocking_fn = |ident: &syn::Ident, output: &syn::ReturnType| {
+        let blocking_ident = format_ident!("{}_blocking", ident);
+        let doc_blocking_terminal_method = doc::blocking_terminal_method(ident);
+        quote! {
+            #doc_blocking_terminal_method
+            #vis fn #blocking_ident(self) #output #where_clause {
+                Self::_optarg_block_on(self.#ident())
+            }
+        }
+    };
+    let blocking_terminal = blocking_fn(ident, output);
+    let try_blocking_terminal = error_ident.map(|error_ident| {
+        let output_ty = return_ty(output);
+        let try_output: syn::ReturnType =
+            syn::parse_quote! { -> core::result::Result<#output_ty, #error_ident> };
+        blocking_fn(&format_ident!("try_{}", ident), &try_output)
+    });
+    quote! {
+        // Executor shared by the blocking terminal methods
+        #[doc(hidden)]
+        fn _optarg_block_on<_OPTARG_FUTURE: core::future::Future>(
+            future: _OPTARG_FUTURE,
+        ) -> _OPTARG_FUTURE::Output {
+            // Wakes the blocked thread by unparking it
+            struct ThreadWaker(std::thread::Thread);
+
+            impl std::task::Wake for ThreadWaker {
+                fn wake(self: std::sync::Arc<Self>) {
+                    self.0.unpark();
+                }
+            }
+
+            let waker = std::task::Waker::from(std::sync::Arc::new(ThreadWaker(
+                std::thread::current(),
+            )));
+            let mut cx = core::task::Context::from_waker(&waker);
+            let mut future = std::boxed::Box::pin(future);
+            loop {
+                match core::future::Future::poll(future.as_mut(), &mut cx) {
+                    core::task::Poll::Ready(value) => return value,
+                    core::task::Poll::Pending => std::thread::park(),
+                }
+            }
+        }
+
+        #blocking_terminal
+        #try_blocking_terminal
+    }
+}
+
+/// Implements `IntoFuture` for the builder. The future returned by the terminal method is boxed
+/// unless it is already boxed by `boxed_future` option.
+pub fn generate_into_future_impl(
+    builder_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    terminal_ident: &syn::Ident,
+    output: &syn::ReturnType,
+    boxed_future: Option<&BoxedFuture>,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates: Vec<&syn::WherePredicate> = where_clause
+        .iter()
+        .flat_map(|w| w.predicates.iter())
+        .collect();
+    let output_ty = return_ty(output);
+    let (future, into_future) = match boxed_future {
+        Some(boxed_future) => (
+            boxed_future.ty(&output_ty),
+            quote! { self.#terminal_ident() },
+        ),
+        None => (
+            BoxedFuture::new(false, generics).ty(&output_ty),
+            quote! { std::boxed::Box::pin(self.#terminal_ident()) },
+        ),
+    };
+    let lifetime = future_lifetime(generics);
+    quote! {
+        impl #impl_generics core::future::IntoFuture for #builder_struct_name #ty_generics
+        where
+            #(#predicates,)*
+            Self: #lifetime,
+        {
+            type Output = #output_ty;
+            type IntoFuture = #future;
+
+            fn into_future(self) -> Self::IntoFuture {
+                #into_future
+            }
+        }
+    }
+}
+
+// Name of the associated constant for the default value given by `const = expr`
+fn default_const_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("DEFAULT_{}", ident.unraw().to_string().to_uppercase())
+}
+
+/// Generates associated constants for `const` default values, and `default_` prefixed functions
+/// for default values not referring to other arguments or environment variables
+pub fn generate_default_items(
+    vis: &syn::Visibility,
+    opt_args: &[&Arg],
+    arg_name: &[&syn::Ident],
+    where_clause: Option<&syn::WhereClause>,
+) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .map(|arg| {
+            let ident = arg.ident;
+            let ty = arg.ty;
+            let opt = arg.opt.as_ref().unwrap();
+            let default_value = &opt.default_value;
+            let default_const = if opt.const_default {
+                let const_ident = default_const_ident(ident);
+                let doc_default_const = doc::default_const(ident);
+                Some(quote! {
+                    #doc_default_const
+                    #vis const #const_ident: #ty = #default_value;
+                })
+            } else {
+                None
+            };
+            let independent = opt.env.is_none()
+                && dependency::referred_idents(default_value)
+                    .iter()
+                    .all(|name| name != "self" && !arg_name.contains(&name));
+            let default_fn = if independent {
+                let default_ident = format_ident!("default_{}", ident);
+                let doc_default_fn = doc::default_fn(ident);
+                let (asyncness, value) = if opt.const_default {
+                    let const_ident = default_const_ident(ident);
+                    (None, quote! { Self::#const_ident })
+                } else if opt.async_default {
+                    (Some(quote! { async }), quote! { (#default_value).await })
+                } else {
+                    (None, quote! { #default_value })
+                };
+                Some(quote! {
+                    #doc_default_fn
+                    #vis #asyncness fn #default_ident() -> #ty #where_clause {
+                        #value
+                    }
+                })
+            } else {
+                None
+            };
+            quote! {
+                #default_const
+                #default_fn
+            }
+        })
+        .collect()
+}
+
+// Generates statements to bind optional arguments with given or default values
+fn generate_opt_bindings(
+    opt_args: &[&Arg],
+    error_ident: Option<&syn::Ident>,
+    overridable: bool,
+) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .map(|arg| {
+            let ident = arg.ident;
+            let ty = arg.ty;
+            let opt = arg.opt.as_ref().unwrap();
+            let mut default_value = if opt.const_default {
+                let const_ident = default_const_ident(ident);
+                quote! { Self::#const_ident }
+            } else if opt.async_default {
+                let default_value = &opt.default_value;
+                quote! { (#default_value).await }
+            } else {
+                let default_value = &opt.default_value;
+                quote! { #default_value }
+            };
+            if let Some(env) = &opt.env {
+                default_value = generate_env_default(arg, env, default_value, error_ident);
+            }
+            if overridable {
+                default_value = overrides::generate_overridden_default(arg, default_value);
+            }
+            if opt.lazy {
+                let lazy_field = lazy_field_ident(ident);
+                quote! {
+                    let #ident: #ty = match (self.#ident, self.#lazy_field) {
+                        (core::option::Option::Some(value), _) => value,
+                        (core::option::Option::None, core::option::Option::Some(f)) => f(),
+                        (core::option::Option::None, core::option::Option::None) => {
+                            #default_value
+                        }
+                    };
+                }
+            } else {
+                quote! {
+                    let #ident: #ty = match self.#ident {
+                        core::option::Option::Some(value) => value,
+                        core::option::Option::None => {
+                            #default_value
+                        }
+                    };
+                }
+            }
+        })
+        .collect()
+}
+
+// Generates expression to read the default value from the environment variable
+fn generate_env_default(
+    arg: &Arg,
+    env: &EnvDefault,
+    default_value: TokenStream,
+    error_ident: Option<&syn::Ident>,
+) -> TokenStream {
+    let ty = arg.ty;
+    let var = &env.var;
+    let on_error = match env.on_error {
+        EnvErrorPolicy::Fallback => quote! { { #default_value } },
+        EnvErrorPolicy::Panic => quote! {
+            panic!("invalid value of environment variable `{}`", #var)
+        },
+        EnvErrorPolicy::Error => {
+            let name = arg.ident.to_string();
+            quote! {
+                return core::result::Result::Err(#error_ident::Env { arg: #name, var: #var })
+            }
+        }
+    };
+    quote! {
+        match std::env::var(#var) {
+            core::result::Result::Err(std::env::VarError::NotPresent) => {
+                #default_value
+            }
+            value => match value
+                .ok()
+                .and_then(|value| <#ty as core::str::FromStr>::from_str(&value).ok())
+            {
+                core::option::Option::Some(value) => value,
+                core::option::Option::None => #on_error,
+            },
+        }
+    }
+}
+
+// Generates statements to return an error if validation of arguments failed
+fn generate_checks(
+    opt_args: &[&Arg],
+    validate: Option<&syn::Path>,
+    arg_name: &[&syn::Ident],
+    error_ident: &syn::Ident,
+) -> Vec<TokenStream> {
+    let mut checks = vec![];
+    for arg in opt_args {
+        let ident = arg.ident;
+        let name = ident.to_string();
+        let opt = arg.opt.as_ref().unwrap();
+        if let Some(range) = &opt.range {
+            checks.push(quote! {
+                if !(#range).contains(&#ident) {
+                    return core::result::Result::Err(#error_ident::OutOfRange { arg: #name });
+                }
+            });
+        }
+        if let Some(validate) = &opt.validate {
+            checks.push(generate_validator_check(
+                quote! { #validate(&#ident) },
+                error_ident,
+                quote! { Validation },
+                Some(&name),
+            ));
+        }
+    }
+    if let Some(validate) = validate {
+        checks.push(generate_validator_check(
+            quote! { #validate(#(&#arg_name),*) },
+            error_ident,
+            quote! { Arguments },
+            None,
+        ));
+    }
+    checks
+}
+
+// Generates a statement to return an error if the validator returned an error
+fn generate_validator_check(
+    call: TokenStream,
+    error_ident: &syn::Ident,
+    variant: TokenStream,
+    arg_name: Option<&str>,
+) -> TokenStream {
+    let fields = arg_name.map(|name| quote! { { arg: #name } });
+    quote! {
+        if #call.is_err() {
+            return core::result::Result::Err(#error_ident::#variant #fields);
+        }
+    }
+}
+
+// Generates statements to return an error if the set arguments violate the groups
+fn generate_group_checks(
+    opt_args: &[&Arg],
+    groups: &ArgGroups,
+    error_ident: &syn::Ident,
+) -> Vec<TokenStream> {
+    let is_set = |ident: &syn::Ident| {
+        let opt = opt_args
+            .iter()
+            .find(|arg| arg.ident == ident)
+            .map(|arg| arg.opt.as_ref().unwrap());
+        let lazy = opt.filter(|opt| opt.lazy).map(|_| {
+            let lazy_field = lazy_field_ident(ident);
+            quote! { || self.#lazy_field.is_some() }
+        });
+        // `Some(None)` of `optarg_none` argument is the same as unset
+        let set = if let Some(OptArgKind::None(_)) = opt.map(|opt| &opt.kind) {
+            quote! {
+                core::matches!(self.#ident, core::option::Option::Some(core::option::Option::Some(_)))
+            }
+        } else {
+            quote! { self.#ident.is_some() }
+        };
+        quote! { (#set #lazy) }
+    };
+    let mut checks = vec![];
+    for group in &groups.conflicts {
+        for (i, arg) in group.iter().enumerate() {
+            for conflicting in &group[i + 1..] {
+                let arg_set = is_set(arg);
+                let conflicting_set = is_set(conflicting);
+                let arg = arg.to_string();
+                let conflicting = conflicting.to_string();
+                checks.push(quote! {
+                    if #arg_set && #conflicting_set {
+                        return core::result::Result::Err(
+                            #error_ident::Conflict { arg: #arg, conflicting: #conflicting }
+                        );
+                    }
+                });
+            }
+        }
+    }
+    for requirement in &groups.requires {
+        let arg_set = is_set(&requirement.arg);
+        let required_set = is_set(&requirement.required);
+        let arg = requirement.arg.to_string();
+        let required = requirement.required.to_string();
+        checks.push(quote! {
+            if #arg_set && !#required_set {
+                return core::result::Result::Err(
+                    #error_ident::MissingRequired { arg: #arg, required: #required }
+                );
+            }
+        });
+    }
+    checks
+}
+
+/// Kinds of argument checks. The error type has a variant for each kind used by the builder.
+pub struct ErrorKinds {
+    conversion: bool,
+    validation: bool,
+    out_of_range: bool,
+    arguments: bool,
+    conflict: bool,
+    missing_required: bool,
+    env: bool,
+}
+
+impl ErrorKinds {
+    pub fn new(opt_args: &[&Arg], validate_all: bool, groups: &ArgGroups) -> ErrorKinds {
+        let opts = || opt_args.iter().map(|arg| arg.opt.as_ref().unwrap());
+        ErrorKinds {
+            conversion: opts().any(|opt| opt.try_into),
+            validation: opts().any(|opt| opt.validate.is_some()),
+            out_of_range: opts().any(|opt| opt.range.is_some()),
+            arguments: validate_all,
+            conflict: !groups.conflicts.is_empty(),
+            missing_required: !groups.requires.is_empty(),
+            env: opts()
+                .any(|opt| matches!(&opt.env, Some(env) if env.on_error == EnvErrorPolicy::Error)),
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.conversion
+            || self.validation
+            || self.out_of_range
+            || self.arguments
+            || self.conflict
+            || self.missing_required
+            || self.env
+    }
+}
+
+/// Generates the error type returned by `try_` prefixed terminal method
+pub fn generate_error_type(
+    vis: &syn::Visibility,
+    error_ident: &syn::Ident,
+    func_name: &syn::Ident,
+    error_kinds: &ErrorKinds,
+) -> TokenStream {
+    let doc_error_type = doc::error_type(func_name);
+    let mut variants = vec![];
+    let mut display_arms = vec![];
+    if error_kinds.conversion {
+        variants.push(quote! {
+            /// Conversion of the value given to `try_` prefixed setter failed.
+            Conversion {
+                /// Argument name
+                arg: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::Conversion { arg } => {
+                write!(f, "conversion failed for argument `{}`", arg)
+            }
+        });
+    }
+    if error_kinds.validation {
+        variants.push(quote! {
+            /// The value is rejected by the validator of the argument.
+            Validation {
+                /// Argument name
+                arg: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::Validation { arg } => {
+                write!(f, "validation failed for argument `{}`", arg)
+            }
+        });
+    }
+    if error_kinds.out_of_range {
+        variants.push(quote! {
+            /// The value is out of the range given to the argument.
+            OutOfRange {
+                /// Argument name
+                arg: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::OutOfRange { arg } => {
+                write!(f, "argument `{}` is out of range", arg)
+            }
+        });
+    }
+    if error_kinds.arguments {
+        variants.push(quote! {
+            /// The arguments are rejected by the validator of the function.
+            Arguments,
+        });
+        display_arms.push(quote! {
+            #error_ident::Arguments => write!(f, "validation failed for arguments"),
+        });
+    }
+    if error_kinds.conflict {
+        variants.push(quote! {
+            /// Conflicting arguments are set together.
+            Conflict {
+                /// Argument name
+                arg: &'static str,
+                /// Name of the argument conflicting with `arg`
+                conflicting: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::Conflict { arg, conflicting } => {
+                write!(f, "argument `{}` conflicts with `{}`", arg, conflicting)
+            }
+        });
+    }
+    if error_kinds.missing_required {
+        variants.push(quote! {
+            /// An argument is set without the argument it requires.
+            MissingRequired {
+                /// Argument name
+                arg: &'static str,
+                /// Name of the argument required by `arg`
+                required: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::MissingRequired { arg, required } => {
+                write!(f, "argument `{}` requires `{}`", arg, required)
+            }
+        });
+    }
+    if error_kinds.env {
+        variants.push(quote! {
+            /// The environment variable giving the default value cannot be parsed.
+            Env {
+                /// Argument name
+                arg: &'static str,
+                /// Environment variable name
+                var: &'static str,
+            },
+        });
+        display_arms.push(quote! {
+            #error_ident::Env { arg, var } => write!(
+                f,
+                "invalid value of environment variable `{}` for argument `{}`",
+                var, arg
+            ),
+        });
+    }
+    quote! {
+        #doc_error_type
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #vis enum #error_ident {
+            #(#variants)*
+        }
+
+        impl core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                match self {
+                    #(#display_arms)*
+                }
+            }
+        }
+    }
+}
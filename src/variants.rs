@@ -0,0 +1,139 @@
+//! Functions to generate setters for each variant of enum arguments
+
+use crate::{doc, Arg};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+const ERR_MSG_NOT_FIELDLESS_ENUM: &str = "(optarg2chain) `OptargVariants` needs a fieldless enum";
+
+// Returns the name of the macro generated by `OptargVariants` derive
+fn callback_macro_ident(enum_ident: &syn::Ident) -> syn::Ident {
+    format_ident!("_optarg_variants_{}", enum_ident)
+}
+
+/// Returns the path of the macro generated by `OptargVariants` for the given enum type
+pub fn callback_macro_path(ty: &syn::Type) -> Option<syn::Path> {
+    let mut path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.clone(),
+        _ => return None,
+    };
+    let segment = path.segments.last_mut()?;
+    segment.ident = callback_macro_ident(&segment.ident);
+    segment.arguments = syn::PathArguments::None;
+    Some(path)
+}
+
+/// Generates the macro passing variant names to `__optarg_variant_setters`
+pub fn derive(input: &syn::DeriveInput) -> Result<TokenStream> {
+    let variants = match &input.data {
+        syn::Data::Enum(data) => &data.variants,
+        _ => return Err(Error::new(input.ident.span(), ERR_MSG_NOT_FIELDLESS_ENUM)),
+    };
+    if let Some(variant) = variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, syn::Fields::Unit))
+    {
+        return Err(Error::new(variant.span(), ERR_MSG_NOT_FIELDLESS_ENUM));
+    }
+    let variant_ident = variants.iter().map(|variant| &variant.ident);
+    let macro_ident = callback_macro_ident(&input.ident);
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #macro_ident {
+            ($($input:tt)*) => {
+                ::optarg2chain::__optarg_variant_setters! { [#(#variant_ident)*] $($input)* }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_ident;
+    })
+}
+
+/// Generates invocations of the macros generated by `OptargVariants` in the impl block of the builder struct
+pub fn generate_variant_setters(vis: &syn::Visibility, opt_args: &[&Arg]) -> Vec<TokenStream> {
+    opt_args
+        .iter()
+        .filter_map(|arg| {
+            let path = arg.opt.as_ref().unwrap().variants.as_ref()?;
+            let ident = arg.ident;
+            let ty = arg.ty;
+            Some(quote! { #path! { #vis #ident #ty } })
+        })
+        .collect()
+}
+
+/// Input of `__optarg_variant_setters` macro
+pub struct VariantSetters {
+    variants: Vec<syn::Ident>,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    ty: syn::TypePath,
+}
+
+impl Parse for VariantSetters {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let content;
+        syn::bracketed!(content in input);
+        let mut variants = vec![];
+        while !content.is_empty() {
+            variants.push(content.parse()?);
+        }
+        Ok(VariantSetters {
+            variants,
+            vis: input.parse()?,
+            ident: input.parse()?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+/// Generates a setter for each variant. They set the value by `maybe_` prefixed setter.
+pub fn generate_setters(input: &VariantSetters) -> TokenStream {
+    let VariantSetters {
+        variants,
+        vis,
+        ident,
+        ty,
+    } = input;
+    let maybe_ident = format_ident!("maybe_{}", ident);
+    let enum_ident = &ty.path.segments.last().unwrap().ident;
+    let setters = variants.iter().map(|variant| {
+        let setter_ident = format_ident!("{}_{}", ident, to_snake_case(&variant.to_string()));
+        let doc_setter = doc::variant_setter(ident, enum_ident, variant);
+        quote! {
+            #doc_setter
+            #vis fn #setter_ident(self) -> Self {
+                self.#maybe_ident(core::option::Option::Some(#ty::#variant))
+            }
+        }
+    });
+    quote! { #(#setters)* }
+}
+
+// Converts `CamelCase` variant name to `snake_case`
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let after_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric());
+            let acronym_end = i > 0
+                && chars[i - 1].is_uppercase()
+                && matches!(chars.get(i + 1), Some(c) if c.is_lowercase());
+            if after_lower || acronym_end {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
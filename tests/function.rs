@@ -1,4 +1,8 @@
+#![allow(clippy::multiple_bound_locations, clippy::needless_lifetimes)]
+
 use optarg2chain::*;
+use std::cell::Cell;
+use std::rc::Rc;
 
 #[optarg_fn(JoinStringBuilder, exec)]
 fn join_strings(
@@ -113,3 +117,923 @@ fn async_test() {
     assert_eq!(block_on(async_fn().exec()), "foo");
     assert_eq!(block_on(async_fn().a("bar").exec()), "bar");
 }
+
+#[optarg_fn(TakeFirst, exec)]
+fn take_first<T: Clone>(v: Vec<T>, #[optarg_none] limit: Option<usize>) -> Vec<T> {
+    match limit {
+        Some(limit) => v.into_iter().take(limit).collect(),
+        None => v,
+    }
+}
+
+#[test]
+fn optarg_none_test() {
+    assert_eq!(take_first(vec![1, 2, 3]).exec(), [1, 2, 3]);
+    assert_eq!(take_first(vec![1, 2, 3]).limit(2u8).exec(), [1, 2]);
+    assert_eq!(take_first(vec![1, 2, 3]).maybe_limit(Some(1)).exec(), [1]);
+    assert_eq!(
+        take_first(vec![1, 2, 3])
+            .limit(1usize)
+            .maybe_limit(None)
+            .exec(),
+        [1, 2, 3]
+    );
+}
+
+#[optarg_fn(Greeting, get)]
+fn greeting(
+    #[optarg("Hello".to_owned())] word: String,
+    #[optarg_default] name: String,
+    #[optarg_none] mark: Option<char>,
+) -> String {
+    let mut s = format!("{}, {}", word, name);
+    if let Some(mark) = mark {
+        s.push(mark);
+    }
+    s
+}
+
+#[test]
+fn conditional_setter_test() {
+    let name: Option<&str> = None;
+    assert_eq!(
+        greeting()
+            .maybe_word(None)
+            .maybe_name(name.map(String::from))
+            .get(),
+        "Hello, "
+    );
+    assert_eq!(
+        greeting()
+            .maybe_word(Some("Hi".to_owned()))
+            .name_if(true, "Bob")
+            .mark_if(false, '!')
+            .get(),
+        "Hi, Bob"
+    );
+    assert_eq!(
+        greeting()
+            .name("Bob")
+            .name_if(false, "Tom")
+            .mark_if(true, '?')
+            .get(),
+        "Hello, Bob?"
+    );
+    assert_eq!(
+        greeting()
+            .word("Hi")
+            .maybe_word(None)
+            .mark('!')
+            .maybe_mark(None)
+            .get(),
+        "Hi, "
+    );
+}
+
+#[optarg_fn(FormatFlags, get)]
+fn format_flags(#[optarg_flag] verbose: bool, #[optarg_flag(true)] color: bool) -> (bool, bool) {
+    (verbose, color)
+}
+
+#[test]
+fn flag_test() {
+    assert_eq!(format_flags().get(), (false, true));
+    assert_eq!(format_flags().verbose().no_color().get(), (true, false));
+    assert_eq!(format_flags().no_color().color().get(), (false, true));
+    assert_eq!(
+        format_flags()
+            .maybe_verbose(None)
+            .maybe_color(Some(false))
+            .get(),
+        (false, false)
+    );
+    assert_eq!(format_flags().verbose_if(false).get(), (false, true));
+    assert_eq!(format_flags().verbose_if(true).get(), (true, true));
+}
+
+type Headers = Vec<(String, String)>;
+
+#[optarg_fn(Request, send)]
+fn request(
+    #[optarg_collect] headers: Headers,
+    #[optarg_collect] tags: std::collections::BTreeSet<u32>,
+    #[optarg_collect] params: std::collections::HashMap<&'static str, i32>,
+) -> (Headers, Vec<u32>, Vec<(&'static str, i32)>) {
+    let mut params: Vec<_> = params.into_iter().collect();
+    params.sort();
+    (headers, tags.into_iter().collect(), params)
+}
+
+#[test]
+fn collect_test() {
+    assert_eq!(request().send(), (vec![], vec![], vec![]));
+    let (headers, tags, params) = request()
+        .insert_headers("a".to_owned(), "b".to_owned())
+        .push_headers(("c".to_owned(), "d".to_owned()))
+        .push_tags(3)
+        .extend_tags(vec![2, 3, 1])
+        .insert_params("x", 1)
+        .extend_params(vec![("y", 2)])
+        .send();
+    assert_eq!(
+        headers,
+        [
+            ("a".to_owned(), "b".to_owned()),
+            ("c".to_owned(), "d".to_owned())
+        ]
+    );
+    assert_eq!(tags, [1, 2, 3]);
+    assert_eq!(params, [("x", 1), ("y", 2)]);
+    let (_, tags, _) = request()
+        .maybe_tags(Some(vec![5].into_iter().collect()))
+        .push_tags(6)
+        .send();
+    assert_eq!(tags, [5, 6]);
+}
+
+#[optarg_fn(Connect, exec)]
+fn connect(
+    host: &'static str,
+    #[optarg(80, try_into)] port: u16,
+    #[optarg(core::num::NonZeroU32::new(1).unwrap(), try_into)] retry: core::num::NonZeroU32,
+) -> String {
+    format!("{}:{} ({})", host, port, retry)
+}
+
+#[test]
+fn try_into_test() {
+    assert_eq!(connect("localhost").exec(), "localhost:80 (1)");
+    assert_eq!(
+        connect("localhost")
+            .try_port(8080u32)
+            .try_retry(3u32)
+            .exec(),
+        "localhost:8080 (3)"
+    );
+    assert_eq!(
+        connect("localhost").try_port(8080u64).try_exec(),
+        Ok("localhost:8080 (1)".to_owned())
+    );
+    assert_eq!(
+        connect("localhost").try_port(-1).try_retry(0u32).try_exec(),
+        Err(ConnectError::Conversion { arg: "port" })
+    );
+    assert_eq!(
+        connect("localhost").try_retry(0u32).try_exec(),
+        Err(ConnectError::Conversion { arg: "retry" })
+    );
+    assert_eq!(
+        connect("localhost")
+            .try_port(-1)
+            .try_exec()
+            .unwrap_err()
+            .to_string(),
+        "conversion failed for argument `port`"
+    );
+    assert_eq!(
+        connect("localhost").try_port(-1).try_port(8080).try_exec(),
+        Ok("localhost:8080 (1)".to_owned())
+    );
+    assert_eq!(
+        connect("localhost").try_port(-1).port(8081u16).try_exec(),
+        Ok("localhost:8081 (1)".to_owned())
+    );
+    assert_eq!(
+        connect("localhost").try_port(-1).reset_port().try_exec(),
+        Ok("localhost:80 (1)".to_owned())
+    );
+    assert_eq!(
+        connect("localhost")
+            .try_port(-1)
+            .try_retry(0u32)
+            .port(8080u16)
+            .try_exec(),
+        Err(ConnectError::Conversion { arg: "retry" })
+    );
+}
+
+#[test]
+#[should_panic(expected = "conversion failed for argument `port`")]
+fn try_into_panic_test() {
+    connect("localhost").try_port(70000).exec();
+}
+
+struct Percent(u32);
+
+impl core::convert::TryFrom<u32> for Percent {
+    type Error = ();
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value <= 100 {
+            Ok(Percent(value))
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[optarg_fn(Scale, exec)]
+fn scale(total: u32, #[optarg(Percent(100), try_into)] percent: Percent) -> u32 {
+    total * percent.0 / 100
+}
+
+// The error of the conversion is not kept, so it needs no trait implementation
+#[test]
+fn try_into_unit_error_test() {
+    assert_eq!(scale(200).try_percent(50u32).try_exec(), Ok(100));
+    assert_eq!(
+        scale(200).try_percent(101u32).try_exec(),
+        Err(ScaleError::Conversion { arg: "percent" })
+    );
+}
+
+#[optarg_fn(AsyncTryInto, exec)]
+async fn async_try_into(#[optarg(1, try_into)] a: u8) -> u8 {
+    a
+}
+
+#[test]
+fn async_try_into_test() {
+    use futures::executor::block_on;
+    assert_eq!(block_on(async_try_into().try_a(2i64).exec()), 2);
+    assert_eq!(
+        block_on(async_try_into().try_a(256).try_exec()),
+        Err(AsyncTryIntoError::Conversion { arg: "a" })
+    );
+}
+
+#[optarg_fn(Apply, exec, setter = exact)]
+fn apply<T: core::ops::Mul<Output = T> + From<u8>>(
+    x: T,
+    #[optarg(T::from(2))] factor: T,
+    #[optarg(None)] offset: Option<T>,
+    #[optarg(Box::new(|x| x))] f: Box<dyn Fn(T) -> T>,
+    #[optarg(Vec::new(), setter = clone_from_ref)] log: Vec<&'static str>,
+    #[optarg(String::new(), setter = into)] name: String,
+) -> (T, usize, String)
+where
+    T: core::ops::Add<Output = T>,
+{
+    let y = f(x * factor);
+    let y = match offset {
+        Some(offset) => y + offset,
+        None => y,
+    };
+    (y, log.len(), name)
+}
+
+#[test]
+fn setter_flavor_test() {
+    assert_eq!(apply(3u8).exec(), (6, 0, "".to_owned()));
+    let log = vec!["a", "b"];
+    assert_eq!(
+        apply(3u8)
+            .factor(3)
+            .offset(Some(1))
+            .f(Box::new(|x| x * 2))
+            .log(&log)
+            .name("x")
+            .exec(),
+        (19, 2, "x".to_owned())
+    );
+    assert_eq!(apply(3u8).offset(None).factor_if(true, 4).exec().0, 12);
+}
+
+#[optarg_fn(Query, run, in_place)]
+fn query<T: Clone + core::fmt::Debug>(
+    table: &'static str,
+    #[optarg_none] limit: Option<T>,
+    #[optarg_flag] distinct: bool,
+    #[optarg_collect] columns: Vec<&'static str>,
+) -> String {
+    format!("{} {:?} {} {:?}", table, limit, distinct, columns)
+}
+
+#[test]
+fn in_place_test() {
+    let mut q = query::<u32>("t");
+    for (i, column) in ["a", "b", "c"].iter().enumerate() {
+        if i % 2 == 0 {
+            q.set_columns(vec![*column]);
+        }
+    }
+    q.set_limit(10u8).set_distinct(true);
+    assert_eq!(q.run_cloned(), r#"t Some(10) true ["c"]"#);
+    q.set_distinct(false);
+    assert_eq!(q.clone().run(), r#"t Some(10) false ["c"]"#);
+    assert_eq!(q.run(), r#"t Some(10) false ["c"]"#);
+}
+
+#[test]
+fn reset_test() {
+    assert_eq!(
+        join_strings("a".to_owned()).b("b").c("c").reset_c().exec(),
+        "abccc"
+    );
+    assert_eq!(
+        join_strings("a".to_owned())
+            .b("b")
+            .c("c")
+            .reset_all()
+            .exec(),
+        "accc"
+    );
+    assert_eq!(
+        connect("localhost").try_port(-1).reset_all().try_exec(),
+        Ok("localhost:80 (1)".to_owned())
+    );
+    assert_eq!(
+        format_flags().verbose().reset_verbose().get(),
+        (false, true)
+    );
+}
+
+#[optarg_fn(Point, build, required_setters, in_place)]
+fn point(x: i32, y: i32, #[optarg(0)] z: i32) -> (i32, i32, i32) {
+    (x, y, z)
+}
+
+#[test]
+fn required_setters_test() {
+    let base = point(1, 2).z(3);
+    assert_eq!(base.clone().x(10).build(), (10, 2, 3));
+    let mut builder = base.y(20);
+    builder.set_x(5);
+    assert_eq!(builder.build(), (5, 20, 3));
+}
+
+mod render_options {
+    use optarg2chain::OptargFlatten;
+
+    #[derive(OptargFlatten, Clone, Debug)]
+    pub struct RenderOptions {
+        pub width: u32,
+        pub height: u32,
+        pub title: String,
+    }
+
+    impl Default for RenderOptions {
+        fn default() -> Self {
+            RenderOptions {
+                width: 80,
+                height: 24,
+                title: String::new(),
+            }
+        }
+    }
+}
+
+use render_options::RenderOptions;
+
+#[optarg_fn(Render, exec)]
+fn render<'a>(
+    text: &'a str,
+    #[optarg_flatten] opts: render_options::RenderOptions,
+    #[optarg_flag] bold: bool,
+) -> String {
+    format!(
+        "{} {}x{} {:?} {}",
+        text, opts.width, opts.height, opts.title, bold
+    )
+}
+
+#[test]
+fn flatten_test() {
+    assert_eq!(render("a").exec(), r#"a 80x24 "" false"#);
+    assert_eq!(
+        render("a").width(100u32).bold().title("t").exec(),
+        r#"a 100x24 "t" true"#
+    );
+    let opts = RenderOptions {
+        width: 1,
+        height: 2,
+        title: "x".to_owned(),
+    };
+    assert_eq!(
+        render("a").opts(opts).height(3u32).exec(),
+        r#"a 1x3 "x" false"#
+    );
+}
+
+#[optarg_fn(RenderExact, exec, setter = exact)]
+fn render_exact(#[optarg_flatten] opts: render_options::RenderOptions) -> (u32, u32, String) {
+    (opts.width, opts.height, opts.title)
+}
+
+#[optarg_fn(RenderRef, exec, setter = clone_from_ref)]
+fn render_ref(#[optarg_flatten] opts: render_options::RenderOptions) -> (u32, String) {
+    (opts.width, opts.title)
+}
+
+#[test]
+fn flatten_setter_flavor_test() {
+    assert_eq!(
+        render_exact().width(1).height(2).exec(),
+        (1, 2, String::new())
+    );
+    let title = "t".to_owned();
+    assert_eq!(render_ref().title(&title).width(&5).exec(), (5, title));
+}
+
+#[derive(PartialEq, Debug)]
+struct RetryPolicy {
+    max: u32,
+    backoff: u64,
+}
+
+#[optarg_fn(RetryPolicyBuilder, build, setter = exact)]
+fn retry_policy(#[optarg(3)] max: u32, #[optarg(10)] backoff: u64) -> RetryPolicy {
+    RetryPolicy { max, backoff }
+}
+
+#[optarg_fn(Fetch, exec, in_place)]
+fn fetch<'a>(
+    url: &'a str,
+    #[optarg_nested(retry_policy(), RetryPolicyBuilder, build)] retry: RetryPolicy,
+) -> (&'a str, RetryPolicy) {
+    (url, retry)
+}
+
+#[test]
+fn nested_test() {
+    assert_eq!(
+        fetch("a").exec(),
+        (
+            "a",
+            RetryPolicy {
+                max: 3,
+                backoff: 10
+            }
+        )
+    );
+    assert_eq!(
+        fetch("a").retry(|r| r.max(5).backoff(20)).exec(),
+        (
+            "a",
+            RetryPolicy {
+                max: 5,
+                backoff: 20
+            }
+        )
+    );
+    assert_eq!(
+        fetch("a").retry_if(false, |r| r.max(5)).exec(),
+        (
+            "a",
+            RetryPolicy {
+                max: 3,
+                backoff: 10
+            }
+        )
+    );
+    let mut builder = fetch("a");
+    builder.set_retry(|r| r.backoff(1));
+    assert_eq!(builder.exec(), ("a", RetryPolicy { max: 3, backoff: 1 }));
+}
+
+mod mode {
+    use optarg2chain::OptargVariants;
+
+    #[derive(OptargVariants, PartialEq, Debug)]
+    pub enum Mode {
+        Fast,
+        Safe,
+        HTTPProxy,
+    }
+}
+
+use mode::{_optarg_variants_Mode, Mode};
+
+#[derive(OptargVariants, PartialEq, Debug)]
+enum Level {
+    Low,
+    High,
+}
+
+#[optarg_fn(Compress, exec, setter = exact)]
+fn compress(
+    #[optarg(Mode::Fast)]
+    #[optarg_variants]
+    mode: Mode,
+    #[optarg_variants]
+    #[optarg(Level::Low)]
+    level: Level,
+) -> (Mode, Level) {
+    (mode, level)
+}
+
+#[test]
+fn variants_test() {
+    assert_eq!(compress().exec(), (Mode::Fast, Level::Low));
+    assert_eq!(
+        compress().mode_safe().level_high().exec(),
+        (Mode::Safe, Level::High)
+    );
+    assert_eq!(
+        compress().mode_http_proxy().mode(Mode::Safe).exec(),
+        (Mode::Safe, Level::Low)
+    );
+}
+
+fn valid_host<S: AsRef<str>>(host: &S) -> Result<(), &'static str> {
+    if host.as_ref().is_empty() {
+        Err("empty host")
+    } else {
+        Ok(())
+    }
+}
+
+fn check_server<S>(name: &&str, _host: &S, _port: &u16, workers: &u8) -> Result<(), &'static str> {
+    if name.is_empty() && *workers > 1 {
+        Err("unnamed server with workers")
+    } else {
+        Ok(())
+    }
+}
+
+#[optarg_fn(Server, exec, validate = check_server)]
+fn server<'a>(
+    name: &'a str,
+    #[optarg("localhost".to_owned(), validate = valid_host)] host: String,
+    #[optarg(8080, range = 1..)] port: u16,
+    #[optarg(1, range = 1..=64)] workers: u8,
+) -> String {
+    format!("{} {}:{} {}", name, host, port, workers)
+}
+
+#[test]
+fn validation_test() {
+    assert_eq!(server("s").try_exec(), Ok("s localhost:8080 1".to_owned()));
+    assert_eq!(
+        server("s").host("").try_exec(),
+        Err(ServerError::Validation { arg: "host" })
+    );
+    assert_eq!(
+        server("s").workers(65).try_exec(),
+        Err(ServerError::OutOfRange { arg: "workers" })
+    );
+    assert_eq!(
+        server("").port(0u16).workers(2).try_exec(),
+        Err(ServerError::OutOfRange { arg: "port" })
+    );
+    assert_eq!(
+        server("").workers(2).try_exec(),
+        Err(ServerError::Arguments)
+    );
+    assert_eq!(
+        server("").workers(2).try_exec().unwrap_err().to_string(),
+        "validation failed for arguments"
+    );
+    assert_eq!(
+        server("s").host("").try_exec().unwrap_err().to_string(),
+        "validation failed for argument `host`"
+    );
+}
+
+fn not_same(a: &i32, b: &i32) -> Result<(), &'static str> {
+    if a == b {
+        Err("same values")
+    } else {
+        Ok(())
+    }
+}
+
+#[optarg_fn(Pair, exec, validate = not_same)]
+fn pair(a: i32, #[optarg(0)] b: i32) -> (i32, i32) {
+    (a, b)
+}
+
+#[test]
+#[should_panic(expected = "validation failed for arguments")]
+fn validation_panic_test() {
+    pair(1).b(1).exec();
+}
+
+#[optarg_fn(
+    Download,
+    exec,
+    conflicts(timeout, deadline),
+    requires(proxy_user => proxy)
+)]
+fn download<'a>(
+    url: &'a str,
+    #[optarg_none] timeout: Option<u32>,
+    #[optarg_none] deadline: Option<u32>,
+    #[optarg_none] proxy: Option<&'a str>,
+    #[optarg_none] proxy_user: Option<&'a str>,
+) -> String {
+    format!(
+        "{} {:?} {:?} {:?} {:?}",
+        url, timeout, deadline, proxy, proxy_user
+    )
+}
+
+#[test]
+fn arg_groups_test() {
+    assert!(download("u").timeout(1u32).try_exec().is_ok());
+    assert!(download("u").proxy("p").proxy_user("a").try_exec().is_ok());
+    assert_eq!(
+        download("u").timeout(1u32).deadline(2u32).try_exec(),
+        Err(DownloadError::Conflict {
+            arg: "timeout",
+            conflicting: "deadline"
+        })
+    );
+    assert_eq!(
+        download("u").proxy_user("a").try_exec(),
+        Err(DownloadError::MissingRequired {
+            arg: "proxy_user",
+            required: "proxy"
+        })
+    );
+    assert!(download("u")
+        .maybe_timeout(None)
+        .deadline(3u32)
+        .try_exec()
+        .is_ok());
+    assert!(download("u")
+        .timeout(1u32)
+        .maybe_timeout(None)
+        .deadline(3u32)
+        .try_exec()
+        .is_ok());
+    assert_eq!(
+        download("u").maybe_proxy(None).proxy_user("a").try_exec(),
+        Err(DownloadError::MissingRequired {
+            arg: "proxy_user",
+            required: "proxy"
+        })
+    );
+    assert_eq!(
+        DownloadError::MissingRequired {
+            arg: "proxy_user",
+            required: "proxy"
+        }
+        .to_string(),
+        "argument `proxy_user` requires `proxy`"
+    );
+}
+
+struct Size {
+    height: u32,
+}
+
+#[optarg_fn(Rect, exec)]
+fn rect(
+    base: u32,
+    #[optarg(width * 2)] height: u32,
+    #[optarg(Size { height: width }.height + 1)] depth: u32,
+    #[optarg(base + 1)] width: u32,
+) -> (u32, u32, u32) {
+    (width - base, height, depth)
+}
+
+#[test]
+fn default_dependency_test() {
+    assert_eq!(rect(1).exec(), (1, 4, 3));
+    assert_eq!(rect(1).width(5u32).exec(), (4, 10, 6));
+    assert_eq!(rect(1).height(1u32).exec(), (1, 1, 3));
+}
+
+#[optarg_fn(Plate, exec)]
+fn plate(
+    heights: Vec<u32>,
+    #[optarg(heights.iter().map(|height| height * 2).sum())] width: u32,
+    #[optarg({ let width = 1; width + heights.len() as u32 })] depth: u32,
+    #[optarg(width * 2)] height: u32,
+) -> (usize, u32, u32, u32) {
+    (heights.len(), width, depth, height)
+}
+
+#[test]
+fn default_local_binding_test() {
+    assert_eq!(plate(vec![1, 2]).exec(), (2, 6, 3, 12));
+    assert_eq!(plate(vec![1, 2]).width(1u32).exec(), (2, 1, 3, 2));
+}
+
+#[optarg_fn(Wait, exec)]
+fn wait(
+    #[optarg(const = 30)] timeout_secs: u64,
+    #[optarg("tick".to_owned())] label: String,
+    #[optarg(timeout_secs / 2)] interval_secs: u64,
+) -> (u64, String, u64) {
+    (timeout_secs, label, interval_secs)
+}
+
+#[test]
+fn default_items_test() {
+    assert_eq!(Wait::DEFAULT_TIMEOUT_SECS, 30);
+    assert_eq!(Wait::default_timeout_secs(), 30);
+    assert_eq!(Wait::default_label(), "tick");
+    assert_eq!(wait().exec(), (30, "tick".to_owned(), 15));
+    assert_eq!(JoinVecBuilder::<i32>::default_c(), [0]);
+}
+
+#[optarg_fn(Token, exec)]
+fn token(
+    #[optarg("ident")] r#type: &'static str,
+    #[optarg(const = 1)] r#loop: u8,
+) -> (&'static str, u8) {
+    (r#type, r#loop)
+}
+
+#[test]
+fn raw_ident_test() {
+    assert_eq!(token().exec(), ("ident", 1));
+    assert_eq!(token().r#type("literal").r#loop(2u8).exec(), ("literal", 2));
+    assert_eq!(Token::DEFAULT_LOOP, 1);
+    assert_eq!(Token::default_type(), "ident");
+}
+
+#[optarg_fn(Encode, exec)]
+#[optarg_preset(fast, quality = 1, threads = 8)]
+#[optarg_preset(best, quality = 9, label = "best".to_owned())]
+fn encode(
+    #[optarg(5)] quality: u8,
+    #[optarg(1)] threads: u32,
+    #[optarg_none] label: Option<String>,
+) -> (u8, u32, Option<String>) {
+    (quality, threads, label)
+}
+
+#[test]
+fn preset_test() {
+    assert_eq!(encode().exec(), (5, 1, None));
+    assert_eq!(encode().fast().exec(), (1, 8, None));
+    assert_eq!(encode().best().exec(), (9, 1, Some("best".to_owned())));
+    assert_eq!(encode().fast().quality(3u8).exec(), (3, 8, None));
+    assert_eq!(encode().threads(2u32).fast().exec(), (1, 8, None));
+}
+
+async fn fetch_token(calls: &std::cell::Cell<u32>) -> String {
+    calls.set(calls.get() + 1);
+    "fetched".to_owned()
+}
+
+#[optarg_fn(Login, exec)]
+async fn login<'a>(
+    calls: &'a std::cell::Cell<u32>,
+    #[optarg(async fetch_token(calls))] token: String,
+    #[optarg(async { 443 })] port: u16,
+    #[optarg(async move { 3 })] retries: u8,
+) -> (String, u16, u8, u32) {
+    (token, port, retries, calls.get())
+}
+
+#[test]
+fn async_default_test() {
+    use futures::executor::block_on;
+    let calls = std::cell::Cell::new(0);
+    assert_eq!(
+        block_on(login(&calls).exec()),
+        ("fetched".to_owned(), 443, 3, 1)
+    );
+    assert_eq!(
+        block_on(login(&calls).token("given").port(80u16).retries(1u8).exec()),
+        ("given".to_owned(), 80, 1, 1)
+    );
+    assert_eq!(block_on(Login::default_port()), 443);
+    assert_eq!(block_on(Login::default_retries()), 3);
+}
+
+#[optarg_fn(RepeatJoin, exec, full)]
+fn repeat_join(
+    mut a: String,
+    #[optarg_default] b: String,
+    #[optarg("ccc".to_owned())] c: String,
+) -> String {
+    if a.len() < 6 {
+        return repeat_join(a.clone() + &a, b, c);
+    }
+    a.push_str(&b);
+    a.push_str(&c);
+    a
+}
+
+#[optarg_fn(Countdown, exec)]
+fn countdown(n: u32, #[optarg(0)] acc: u32) -> u32 {
+    if n == 0 {
+        acc
+    } else {
+        countdown(n - 1, acc + n)
+    }
+}
+
+// Local bindings named `sum_to` are called as they are
+#[optarg_fn(SumTo, exec)]
+fn sum_to(a: u32, #[optarg(0)] b: u32) -> (u32, u32, u32, u32) {
+    let total = if a == 0 { b } else { sum_to(a - 1, b).0 + a };
+    let by_let = {
+        let sum_to = |a: u32, b: u32| a * b;
+        sum_to(a, b)
+    };
+    let by_param = [u32::max].iter().map(|sum_to| sum_to(a, b)).sum();
+    let by_arm = match Some(u32::min) {
+        Some(sum_to) => sum_to(a, b),
+        None => 0,
+    };
+    (total, by_let, by_param, by_arm)
+}
+
+// Attributes other than the document are kept on `shadowed_full`
+#[optarg_fn(Shadowed, exec, full)]
+#[inline]
+#[allow(clippy::let_and_return)]
+fn shadowed(a: u32, #[optarg(1)] b: u32) -> (u32, u32) {
+    let outer = if a == 0 { 0 } else { shadowed(a - 1, b).0 + b };
+    let shadowed = |a: u32, b: u32| (a * 10, b * 10);
+    let result = (outer, shadowed(a, b).0);
+    result
+}
+
+#[test]
+fn full_shadowing_test() {
+    assert_eq!(sum_to(4).exec(), (10, 0, 4, 0));
+    assert_eq!(sum_to(3).b(5u32).exec(), (11, 15, 5, 3));
+    assert_eq!(shadowed(3).exec(), (3, 30));
+    assert_eq!(shadowed_full(2, 2), (4, 20));
+}
+
+#[test]
+fn full_arity_test() {
+    assert_eq!(repeat_join("aaa".into()).exec(), "aaaaaaccc");
+    assert_eq!(
+        repeat_join_full("aaaaaa".into(), "b".into(), "c".into()),
+        "aaaaaabc"
+    );
+    let f: fn(String, String, String) -> String = repeat_join_full;
+    assert_eq!(f("xy".into(), "".into(), "".into()), "xyxyxyxy");
+    assert_eq!(countdown(4).exec(), 10);
+}
+
+#[optarg_fn(Compile, exec)]
+fn compile(
+    src: &'static str,
+    #[optarg("default".to_owned(), lazy)] name: String,
+    #[optarg(1, lazy, try_into)] level: u8,
+) -> String {
+    format!("{}:{}:{}", src, name, level)
+}
+
+#[test]
+fn lazy_test() {
+    let called = Rc::new(Cell::new(0));
+    let counter = called.clone();
+    assert_eq!(
+        compile("a")
+            .name_with(move || {
+                counter.set(counter.get() + 1);
+                "lazy".to_owned()
+            })
+            .exec(),
+        "a:lazy:1"
+    );
+    assert_eq!(called.get(), 1);
+
+    let counter = called.clone();
+    assert_eq!(
+        compile("b")
+            .name_with(move || {
+                counter.set(counter.get() + 1);
+                "lazy".to_owned()
+            })
+            .name("eager")
+            .level_with(|| 3)
+            .try_level(4u64)
+            .exec(),
+        "b:eager:4"
+    );
+    assert_eq!(called.get(), 1);
+    assert_eq!(
+        compile("c")
+            .name("eager")
+            .name_with(|| "lazy".to_owned())
+            .exec(),
+        "c:lazy:1"
+    );
+    assert_eq!(compile("d").exec(), "d:default:1");
+    let name = "borrowed".to_owned();
+    assert_eq!(
+        compile("e").name_with(|| name.clone()).exec(),
+        "e:borrowed:1"
+    );
+    assert_eq!(
+        compile("f").try_level(256).level_with(|| 2).try_exec(),
+        Ok("f:default:2".to_owned())
+    );
+}
+
+#[test]
+fn lazy_reset_test() {
+    assert_eq!(
+        compile("a")
+            .name_with(|| "lazy".to_owned())
+            .reset_name()
+            .exec(),
+        "a:default:1"
+    );
+    assert_eq!(
+        compile("a").level_with(|| 5).reset_all().exec(),
+        "a:default:1"
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_lifetimes)]
+
 use optarg2chain::*;
 
 #[derive(PartialEq, Debug)]
@@ -21,6 +23,21 @@ impl Integer {
         self
     }
 
+    #[optarg_method(MulBuilder, exec)]
+    fn mul<'a>(&'a self, #[optarg_none] a: Option<i32>) -> Option<i32> {
+        a.map(|a| self.0 * a)
+    }
+
+    #[optarg_method(ShiftBuilder, exec)]
+    fn shift<'a>(&'a self, #[optarg(1, try_into)] n: u32) -> i32 {
+        self.0 << n
+    }
+
+    #[optarg_method(MulAddBuilder, exec, setter = exact)]
+    fn mul_add<'a>(&'a self, #[optarg(1)] a: u8, #[optarg(None)] b: Option<i32>) -> i32 {
+        self.0 * i32::from(a) + b.unwrap_or(0)
+    }
+
     #[optarg_method(IntegerNew, build)]
     fn new() -> Integer {
         Integer(42)
@@ -39,6 +56,25 @@ fn integer_test() {
     assert_eq!(integer.0, 100);
     assert_eq!(integer.add_and_take().a(27).exec(), Integer(127));
     assert_eq!(Integer::new().build(), Integer(42));
+    let integer = Integer(3);
+    assert_eq!(integer.mul().exec(), None);
+    assert_eq!(integer.mul().a(5i8).exec(), Some(15));
+    assert_eq!(integer.mul().maybe_a(Some(7)).exec(), Some(21));
+    assert_eq!(MulBuilder::default_a(), None);
+    assert_eq!(integer.add().maybe_a(None).exec(), 23);
+    assert_eq!(integer.add().maybe_a(Some(1)).exec(), 4);
+    assert_eq!(integer.add().a_if(false, 1).exec(), 23);
+    assert_eq!(integer.add().a_if(true, 1).exec(), 4);
+    assert_eq!(integer.shift().exec(), 6);
+    assert_eq!(integer.mul_add().a(2).b(None).exec(), 6);
+    assert_eq!(integer.mul_add().b(Some(1)).exec(), 4);
+    assert_eq!(integer.mul_add().a(2).b(Some(1)).reset_a().exec(), 4);
+    assert_eq!(integer.mul_add().a(2).b(Some(1)).reset_all().exec(), 3);
+    assert_eq!(integer.shift().try_n(2i8).try_exec(), Ok(12));
+    assert_eq!(
+        integer.shift().try_n(-2i8).try_exec(),
+        Err(ShiftBuilderError::Conversion { arg: "n" })
+    );
 }
 
 #[derive(PartialEq, Debug)]
@@ -89,6 +125,36 @@ impl<T: Clone> MyVec<T> {
     }
 }
 
+#[optarg_impl]
+impl<T: Clone + Default> MyVec<T> {
+    #[optarg_method(Fill, exec, in_place, setter = exact)]
+    fn fill<'a>(&'a mut self, #[optarg_default] value: T, #[optarg(1)] count: usize) {
+        for _ in 0..count {
+            self.data.push(value.clone());
+        }
+    }
+
+    #[optarg_method(WithLen, build, in_place, setter = exact)]
+    fn with_len(#[optarg(0)] len: usize) -> Self {
+        MyVec {
+            data: vec![T::default(); len],
+        }
+    }
+}
+
+#[test]
+fn myvec_in_place_test() {
+    let mut myvec = MyVec { data: vec![1] };
+    let mut fill = myvec.fill();
+    fill.set_value(3).set_count(2);
+    fill.exec();
+    assert_eq!(myvec.data, [1, 3, 3]);
+    let mut builder = MyVec::<u8>::with_len();
+    builder.set_len(2);
+    assert_eq!(builder.build_cloned().data, [0, 0]);
+    assert_eq!(builder.len(3).build().data, [0, 0, 0]);
+}
+
 #[test]
 fn myvec_test() {
     let myvec = MyVec {
@@ -129,6 +195,11 @@ impl<'a, 'b> TwoStr<'a, 'b> {
         TwoStr { a, b }
     }
 
+    #[optarg_method(TwoStrNewAll, build, required_setters)]
+    fn new_all(a: &'a str, b: &'b str) -> TwoStr<'a, 'b> {
+        TwoStr { a, b }
+    }
+
     #[optarg_method(TwoStrReplace, exec)]
     fn replace<'s, 'c>(&'s self, #[optarg("ccc")] b: &'c str) -> TwoStr<'a, 'c> {
         TwoStr { a: self.a, b }
@@ -149,6 +220,10 @@ fn twostr_test() {
         TwoStr { a: "x", b: "y" }
     );
     assert_eq!(TwoStr::new_static("x").build(), TwoStr { a: "x", b: "" });
+    assert_eq!(
+        TwoStr::new_all("x", "y").b("z").build(),
+        TwoStr { a: "x", b: "z" }
+    );
     let two_str = TwoStr { a: "aaa", b: "bbb" };
     assert_eq!(
         two_str.replace().b("yyy").exec(),
@@ -161,6 +236,141 @@ fn twostr_test() {
     );
 }
 
+#[derive(OptargFlatten, Default)]
+struct Margin {
+    left: i32,
+    right: i32,
+}
+
+struct Line {
+    len: i32,
+}
+
+#[optarg_impl]
+impl Line {
+    #[optarg_method(LineInner, get)]
+    fn inner<'a>(&'a self, #[optarg_flatten] margin: Margin) -> i32 {
+        self.len - margin.left - margin.right
+    }
+}
+
+#[test]
+fn flatten_method_test() {
+    let line = Line { len: 10 };
+    assert_eq!(line.inner().get(), 10);
+    assert_eq!(line.inner().left(2).right(3).get(), 5);
+}
+
+#[derive(OptargVariants, Clone, Copy, PartialEq, Debug)]
+enum Align {
+    Left,
+    Right,
+}
+
+#[optarg_impl]
+impl Line {
+    #[optarg_method(LineAlign, get)]
+    fn align<'a>(
+        &'a self,
+        #[optarg(Align::Left)]
+        #[optarg_variants]
+        align: Align,
+    ) -> (i32, Align) {
+        (self.len, align)
+    }
+}
+
+#[test]
+fn variants_method_test() {
+    let line = Line { len: 10 };
+    assert_eq!(line.align().get(), (10, Align::Left));
+    assert_eq!(line.align().align_right().get(), (10, Align::Right));
+}
+
+fn shorter_than_line(left: &i32, right: &i32) -> Result<(), &'static str> {
+    if left + right < 10 {
+        Ok(())
+    } else {
+        Err("padding longer than line")
+    }
+}
+
+#[optarg_impl]
+impl Line {
+    #[optarg_method(LinePad, get, validate = shorter_than_line)]
+    fn pad<'a>(&'a self, #[optarg(0, range = 0..)] left: i32, #[optarg(0)] right: i32) -> i32 {
+        self.len - left - right
+    }
+}
+
+#[test]
+fn validation_method_test() {
+    let line = Line { len: 10 };
+    assert_eq!(line.pad().left(2).try_get(), Ok(8));
+    assert_eq!(
+        line.pad().left(-1).try_get(),
+        Err(LinePadError::OutOfRange { arg: "left" })
+    );
+    assert_eq!(
+        line.pad().left(5).right(5).try_get(),
+        Err(LinePadError::Arguments)
+    );
+}
+
+struct Canvas;
+
+#[optarg_impl]
+impl Canvas {
+    #[optarg_method(CanvasStroke, draw)]
+    #[optarg_preset(bold, width = 3)]
+    fn stroke<'a>(&'a self, #[optarg(1)] width: u32, #[optarg_flag] dashed: bool) -> (u32, bool) {
+        (width, dashed)
+    }
+}
+
+#[test]
+fn preset_method_test() {
+    assert_eq!(Canvas.stroke().bold().draw(), (3, false));
+    assert_eq!(
+        Canvas.stroke().bold().dashed().width(2u32).draw(),
+        (2, true)
+    );
+}
+
+struct Digits(u32);
+
+#[optarg_impl]
+impl Digits {
+    // Attributes other than the document are kept on `sum_full`
+    #[optarg_method(DigitsSum, exec, full)]
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn sum<'a>(&'a self, #[optarg(0)] acc: u32) -> u32 {
+        let sum = if self.0 == 0 {
+            acc
+        } else {
+            Self::sum(&Digits(self.0 / 10), acc + self.0 % 10)
+        };
+        sum
+    }
+
+    #[optarg_method(DigitsCount, exec)]
+    fn count_up<'a>(&'a self, #[optarg(0)] from: u32) -> u32 {
+        if from >= self.0 {
+            from
+        } else {
+            self.count_up(from + 1)
+        }
+    }
+}
+
+#[test]
+fn full_arity_method_test() {
+    assert_eq!(Digits(1234).sum().exec(), 10);
+    assert_eq!(Digits(1234).sum_full(5), 15);
+    assert_eq!(Digits(3).count_up().exec(), 3);
+}
+
 struct AsyncTest;
 
 #[optarg_impl]
@@ -178,3 +388,23 @@ fn async_test() {
     assert_eq!(block_on(a.async_fn().exec()), 3);
     assert_eq!(block_on(a.async_fn().a(6).exec()), 6);
 }
+
+struct Cache(u32);
+
+#[optarg_impl]
+impl Cache {
+    #[optarg_method(CacheGet, get)]
+    fn get<'a>(&'a self, #[optarg(0, lazy)] fallback: u32) -> u32 {
+        if self.0 > 0 {
+            self.0
+        } else {
+            fallback
+        }
+    }
+}
+
+#[test]
+fn lazy_method_test() {
+    assert_eq!(Cache(0).get().fallback_with(|| 5).get(), 5);
+    assert_eq!(Cache(2).get().get(), 2);
+}
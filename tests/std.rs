@@ -0,0 +1,297 @@
+#![cfg(feature = "std")]
+#![allow(clippy::needless_lifetimes)]
+
+use optarg2chain::*;
+use std::rc::Rc;
+
+#[optarg_fn(Timeout, get)]
+fn timeout(
+    #[optarg(env = "OPTARG2CHAIN_TEST_TIMEOUT", default = 30)] timeout: u64,
+    #[optarg(env = "OPTARG2CHAIN_TEST_RETRIES", default = 3, env_error = error)] retries: u8,
+) -> (u64, u8) {
+    (timeout, retries)
+}
+
+#[optarg_fn(Port, get)]
+fn port(
+    #[optarg(env = "OPTARG2CHAIN_TEST_PORT", default = 80, env_error = panic)] port: u16,
+) -> u16 {
+    port
+}
+
+// Environment variables are shared by the tests running in parallel. Each test uses its own
+// variables, and holds this lock while it changes them.
+static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn lock_env() -> std::sync::MutexGuard<'static, ()> {
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[test]
+fn env_test() {
+    let _guard = lock_env();
+    assert_eq!(timeout().get(), (30, 3));
+    std::env::set_var("OPTARG2CHAIN_TEST_TIMEOUT", "10");
+    assert_eq!(timeout().get(), (10, 3));
+    assert_eq!(timeout().timeout(5u64).get(), (5, 3));
+    std::env::set_var("OPTARG2CHAIN_TEST_TIMEOUT", "x");
+    assert_eq!(timeout().get(), (30, 3));
+    std::env::set_var("OPTARG2CHAIN_TEST_RETRIES", "-1");
+    assert_eq!(
+        timeout().try_get(),
+        Err(TimeoutError::Env {
+            arg: "retries",
+            var: "OPTARG2CHAIN_TEST_RETRIES"
+        })
+    );
+    assert_eq!(timeout().retries(1u8).try_get(), Ok((30, 1)));
+    std::env::remove_var("OPTARG2CHAIN_TEST_TIMEOUT");
+    std::env::remove_var("OPTARG2CHAIN_TEST_RETRIES");
+}
+
+#[test]
+#[should_panic(expected = "invalid value of environment variable `OPTARG2CHAIN_TEST_PORT`")]
+fn env_panic_test() {
+    let _guard = lock_env();
+    std::env::set_var("OPTARG2CHAIN_TEST_PORT", "port");
+    port().get();
+}
+
+#[optarg_fn(JoinStringsBuilder, exec, overridable)]
+fn join_strings(
+    mut a: String,
+    #[optarg_default] b: String,
+    #[optarg("ccc".to_owned())] c: String,
+) -> String {
+    a.push_str(&b);
+    a.push_str(&c);
+    a
+}
+
+#[test]
+fn overridable_test() {
+    assert_eq!(join_strings("aaa".into()).exec(), "aaaccc");
+    let joined = JoinStringsBuilder::with_default_c("zzz", || {
+        assert_eq!(
+            JoinStringsBuilder::with_default_b("yyy", || join_strings("aaa".into()).exec()),
+            "aaayyyzzz"
+        );
+        join_strings("aaa".into()).exec()
+    });
+    assert_eq!(joined, "aaazzz");
+    assert_eq!(
+        JoinStringsBuilder::with_default_c("zzz", || join_strings("aaa".into()).c("xxx").exec()),
+        "aaaxxx"
+    );
+    assert_eq!(join_strings("aaa".into()).exec(), "aaaccc");
+}
+
+#[test]
+fn overridable_panic_test() {
+    let result = std::panic::catch_unwind(|| {
+        JoinStringsBuilder::with_default_c("zzz", || panic!("in scope"));
+    });
+    assert!(result.is_err());
+    assert_eq!(join_strings("aaa".into()).exec(), "aaaccc");
+}
+
+#[optarg_fn(PairBuilder, exec, overridable)]
+fn pair<T: Clone + Default>(a: T, #[optarg_default] b: T) -> (T, T) {
+    (a, b)
+}
+
+#[test]
+fn overridable_generic_test() {
+    assert_eq!(pair(1).exec(), (1, 0));
+    let result = PairBuilder::<i32>::with_default_b(5, || {
+        assert_eq!(pair("a".to_owned()).exec(), ("a".to_owned(), String::new()));
+        PairBuilder::<String>::with_default_b("b".to_owned(), || {
+            assert_eq!(
+                pair("a".to_owned()).exec(),
+                ("a".to_owned(), "b".to_owned())
+            );
+            pair(1).exec()
+        })
+    });
+    assert_eq!(result, (1, 5));
+    assert_eq!(pair(1).exec(), (1, 0));
+}
+
+struct Greeter;
+
+#[optarg_impl]
+impl Greeter {
+    #[optarg_method(GreetBuilder, exec, overridable)]
+    fn greet<'a>(&'a self, #[optarg("hello".to_owned())] word: String) -> String {
+        word
+    }
+}
+
+#[test]
+fn overridable_method_test() {
+    assert_eq!(Greeter.greet().exec(), "hello");
+    assert_eq!(
+        GreetBuilder::with_default_word("hi", || Greeter.greet().exec()),
+        "hi"
+    );
+}
+
+#[optarg_fn(Fetch, exec, into_future)]
+async fn fetch<'a>(url: &'a str, #[optarg(3)] retries: u8) -> (&'a str, u8) {
+    (url, retries)
+}
+
+#[optarg_fn(Sum, exec, into_future)]
+async fn sum<T: core::ops::Add<Output = T> + Default>(a: T, #[optarg_default] b: T) -> T {
+    a + b
+}
+
+struct Client(Vec<u8>);
+
+#[optarg_impl]
+impl Client {
+    #[optarg_method(ClientGet, exec, into_future)]
+    async fn get<'a>(&'a self, #[optarg(1)] times: usize) -> &'a [u8] {
+        &self.0[..times]
+    }
+}
+
+// The future lives as long as `'a`, so the other lifetime must outlive it
+#[optarg_fn(Pick, exec, into_future, boxed_future)]
+async fn pick<'a, 'b: 'a>(first: &'a str, second: &'b str, #[optarg_flag] swap: bool) -> &'a str {
+    if swap {
+        second
+    } else {
+        first
+    }
+}
+
+#[test]
+fn into_future_test() {
+    use futures::executor::block_on;
+    assert_eq!(block_on(async { fetch("a").await }), ("a", 3));
+    assert_eq!(block_on(async { fetch("a").retries(1u8).await }), ("a", 1));
+    assert_eq!(block_on(fetch("b").exec()), ("b", 3));
+    assert_eq!(block_on(async { sum(1).b(2).await }), 3);
+    let client = Client(vec![1, 2, 3]);
+    assert_eq!(block_on(async { client.get().await }), [1]);
+    assert_eq!(block_on(async { client.get().times(2usize).await }), [1, 2]);
+    assert_eq!(block_on(async { pick("a", "b").await }), "a");
+    assert_eq!(
+        block_on(assert_send(std::future::IntoFuture::into_future(
+            pick("a", "b").swap()
+        ))),
+        "b"
+    );
+}
+
+#[optarg_fn(Fib, exec, boxed_future)]
+async fn fib(n: u32, #[optarg(0)] offset: u32) -> u32 {
+    if n < 2 {
+        n + offset
+    } else {
+        fib(n - 1).exec().await + fib(n - 2).exec().await + offset
+    }
+}
+
+#[optarg_fn(Shared, exec, boxed_future(?Send), into_future)]
+async fn shared(value: Rc<u8>, #[optarg(1)] times: u8, #[optarg(0, range = ..10)] extra: u8) -> u8 {
+    *value * times + extra
+}
+
+struct Tree(Vec<Tree>);
+
+#[optarg_impl]
+impl Tree {
+    #[optarg_method(TreeCount, exec, boxed_future)]
+    async fn count<'a>(&'a self, #[optarg(1)] weight: usize) -> usize {
+        let mut count = weight;
+        for child in &self.0 {
+            count += child.count().weight(weight).exec().await;
+        }
+        count
+    }
+}
+
+fn assert_send<F: Send>(future: F) -> F {
+    future
+}
+
+#[test]
+fn boxed_future_test() {
+    use futures::executor::block_on;
+    assert_eq!(block_on(assert_send(fib(10).exec())), 55);
+    assert_eq!(block_on(fib(3).offset(1u32).exec()), 3);
+
+    let value = Rc::new(2);
+    assert_eq!(block_on(shared(value.clone()).exec()), 2);
+    assert_eq!(
+        block_on(async { shared(value.clone()).times(3u8).await }),
+        6
+    );
+    assert_eq!(
+        block_on(shared(value).extra(10u8).try_exec()),
+        Err(SharedError::OutOfRange { arg: "extra" })
+    );
+
+    let tree = Tree(vec![Tree(vec![]), Tree(vec![Tree(vec![])])]);
+    assert_eq!(block_on(assert_send(tree.count().exec())), 4);
+    assert_eq!(block_on(tree.count().weight(2usize).exec()), 8);
+}
+
+#[optarg_fn(Receive, exec, blocking)]
+async fn receive(
+    rx: futures::channel::oneshot::Receiver<u32>,
+    #[optarg(0, validate = is_small)] offset: u32,
+) -> u32 {
+    rx.await.unwrap() + offset
+}
+
+fn is_small(value: &u32) -> Result<(), &'static str> {
+    if *value < 100 {
+        Ok(())
+    } else {
+        Err("too large")
+    }
+}
+
+struct Counter(u32);
+
+#[optarg_impl]
+impl Counter {
+    #[optarg_method(CounterAdd, exec, blocking, boxed_future)]
+    async fn add<'a>(&'a self, #[optarg(1)] n: u32) -> u32 {
+        self.0 + n
+    }
+}
+
+#[test]
+fn blocking_test() {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let sender = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        tx.send(5).unwrap();
+    });
+    assert_eq!(receive(rx).offset(1u32).exec_blocking(), 6);
+    sender.join().unwrap();
+
+    let (_tx, rx) = futures::channel::oneshot::channel();
+    assert_eq!(
+        receive(rx).offset(100u32).try_exec_blocking(),
+        Err(ReceiveError::Validation { arg: "offset" })
+    );
+    let (_tx, rx) = futures::channel::oneshot::channel();
+    assert_eq!(
+        receive(rx)
+            .offset(100u32)
+            .try_exec_blocking()
+            .unwrap_err()
+            .to_string(),
+        "validation failed for argument `offset`"
+    );
+
+    assert_eq!(Counter(1).add().exec_blocking(), 2);
+    assert_eq!(Counter(1).add().n(3u32).exec_blocking(), 4);
+}